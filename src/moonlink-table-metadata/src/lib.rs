@@ -0,0 +1,21 @@
+//! Shared, serializable metadata records describing how a mooncake table's
+//! rows map onto its iceberg data files and deletion vectors. Kept in its
+//! own crate so it can be depended on by both the storage engine and
+//! external tooling without pulling in the rest of moonlink.
+
+/// A single deleted-row position within a data file, as recorded directly in
+/// an iceberg manifest entry (as opposed to inside a puffin deletion-vector
+/// blob).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionDelete {
+    pub data_file_number: u32,
+    pub data_file_row_number: u32,
+}
+
+/// Associates a data file with the puffin file holding its deletion-vector
+/// blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeletionVector {
+    pub data_file_number: u32,
+    pub puffin_file_number: u32,
+}
@@ -0,0 +1,41 @@
+use std::io;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Crate-wide result alias.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error at {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("io error: {0}")]
+    PlainIo(#[from] io::Error),
+
+    #[error("arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+
+    #[error("parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    #[error("iceberg error: {0}")]
+    Iceberg(#[from] iceberg::Error),
+
+    #[error("{0}")]
+    Other(String),
+}
+
+impl Error {
+    pub fn io_with_path(source: io::Error, path: impl Into<PathBuf>) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+}
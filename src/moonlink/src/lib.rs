@@ -0,0 +1,12 @@
+pub mod error;
+pub mod row;
+pub mod storage;
+
+pub use error::{Error, Result};
+pub use row::{IdentityProp, MoonlinkRow, RowValue};
+pub use storage::filesystem::accessor_config::{AccessorConfig, CacheReadMode};
+pub use storage::filesystem::storage_config::StorageConfig;
+pub use storage::iceberg::iceberg_table_config::IcebergCatalogConfig;
+pub use storage::mooncake_table::{MooncakeTable, MooncakeTableConfig, TableEvent};
+pub use storage::object_storage_cache::{NonEvictableHandle, ObjectStorageCache};
+pub use storage::wal::{WalConfig, WalManager};
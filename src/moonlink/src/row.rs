@@ -0,0 +1,141 @@
+/// A single cell value within a [`MoonlinkRow`].
+///
+/// This mirrors the subset of Postgres/Arrow scalar types moonlink currently
+/// replicates; it is intentionally not exhaustive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowValue {
+    Int32(i32),
+    Int64(i64),
+    Float64(u64),
+    ByteArray(Vec<u8>),
+    Bool(bool),
+    Null,
+}
+
+const TAG_INT32: u8 = 0;
+const TAG_INT64: u8 = 1;
+const TAG_FLOAT64: u8 = 2;
+const TAG_BYTE_ARRAY: u8 = 3;
+const TAG_BOOL: u8 = 4;
+const TAG_NULL: u8 = 5;
+
+impl RowValue {
+    fn encode_into(&self, buf: &mut Vec<u8>) {
+        match self {
+            RowValue::Int32(v) => {
+                buf.push(TAG_INT32);
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+            RowValue::Int64(v) => {
+                buf.push(TAG_INT64);
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+            RowValue::Float64(v) => {
+                buf.push(TAG_FLOAT64);
+                buf.extend_from_slice(&v.to_le_bytes());
+            }
+            RowValue::ByteArray(bytes) => {
+                buf.push(TAG_BYTE_ARRAY);
+                buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+                buf.extend_from_slice(bytes);
+            }
+            RowValue::Bool(v) => {
+                buf.push(TAG_BOOL);
+                buf.push(*v as u8);
+            }
+            RowValue::Null => buf.push(TAG_NULL),
+        }
+    }
+
+    fn decode_from(buf: &[u8]) -> (Self, usize) {
+        match buf[0] {
+            TAG_INT32 => (
+                RowValue::Int32(i32::from_le_bytes(buf[1..5].try_into().unwrap())),
+                5,
+            ),
+            TAG_INT64 => (
+                RowValue::Int64(i64::from_le_bytes(buf[1..9].try_into().unwrap())),
+                9,
+            ),
+            TAG_FLOAT64 => (
+                RowValue::Float64(u64::from_le_bytes(buf[1..9].try_into().unwrap())),
+                9,
+            ),
+            TAG_BYTE_ARRAY => {
+                let len = u32::from_le_bytes(buf[1..5].try_into().unwrap()) as usize;
+                let bytes = buf[5..5 + len].to_vec();
+                (RowValue::ByteArray(bytes), 5 + len)
+            }
+            TAG_BOOL => (RowValue::Bool(buf[1] != 0), 2),
+            TAG_NULL => (RowValue::Null, 1),
+            other => panic!("unknown RowValue tag {other}"),
+        }
+    }
+}
+
+/// A moonlink row: an ordered tuple of [`RowValue`]s matching the table schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoonlinkRow {
+    values: Vec<RowValue>,
+}
+
+impl MoonlinkRow {
+    pub fn new(values: Vec<RowValue>) -> Self {
+        Self { values }
+    }
+
+    pub fn values(&self) -> &[RowValue] {
+        &self.values
+    }
+
+    /// Estimate the in-memory size of this row in bytes, used by the spill
+    /// subsystem to decide when an in-memory batch set has grown too large.
+    pub fn estimated_size_bytes(&self) -> usize {
+        self.values
+            .iter()
+            .map(|v| match v {
+                RowValue::Int32(_) => 4,
+                RowValue::Int64(_) => 8,
+                RowValue::Float64(_) => 8,
+                RowValue::Bool(_) => 1,
+                RowValue::Null => 0,
+                RowValue::ByteArray(bytes) => bytes.len(),
+            })
+            .sum()
+    }
+
+    /// Appends a length-prefixed encoding of this row to `buf`, used by the
+    /// spill subsystem to persist overflow rows to segment files.
+    pub fn encode_into(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&(self.values.len() as u32).to_le_bytes());
+        for value in &self.values {
+            value.encode_into(buf);
+        }
+    }
+
+    /// Decodes a single row previously written by [`Self::encode_into`],
+    /// returning the row and the number of bytes consumed from `buf`.
+    pub fn decode_from(buf: &[u8]) -> (Self, usize) {
+        let mut offset = 0;
+        let count = u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap()) as usize;
+        offset += 4;
+        let mut values = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (value, consumed) = RowValue::decode_from(&buf[offset..]);
+            values.push(value);
+            offset += consumed;
+        }
+        (Self { values }, offset)
+    }
+}
+
+/// How row identity (the "primary key" used for upserts/deletes) is derived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdentityProp {
+    /// No identity; the table is append-only.
+    None,
+    /// Identity is the full row.
+    FullRow,
+    /// Identity is a fixed set of column indices.
+    Keys(Vec<usize>),
+}
@@ -0,0 +1,52 @@
+use std::path::{Path, PathBuf};
+
+use super::accessor_config::AccessorConfig;
+use super::storage_config::StorageConfig;
+use crate::{Error, Result};
+
+/// Reads and writes table files against whatever backend an
+/// [`AccessorConfig`] points at. Only local filesystem storage is currently
+/// implemented; other backends are expected to be added as variants of
+/// [`StorageConfig`] alongside a matching arm here.
+#[derive(Debug, Clone)]
+pub struct FileSystemAccessor {
+    config: AccessorConfig,
+}
+
+impl FileSystemAccessor {
+    pub fn new(config: AccessorConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &AccessorConfig {
+        &self.config
+    }
+
+    fn resolve(&self, relative_path: &str) -> Result<PathBuf> {
+        match &self.config.storage_config {
+            StorageConfig::FileSystem { root_directory, .. } => {
+                Ok(Path::new(root_directory).join(relative_path))
+            }
+        }
+    }
+
+    pub async fn write_bytes(&self, relative_path: &str, bytes: &[u8]) -> Result<PathBuf> {
+        let full_path = self.resolve(relative_path)?;
+        if let Some(parent) = full_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| Error::io_with_path(e, parent))?;
+        }
+        tokio::fs::write(&full_path, bytes)
+            .await
+            .map_err(|e| Error::io_with_path(e, &full_path))?;
+        Ok(full_path)
+    }
+
+    pub async fn read_bytes(&self, relative_path: &str) -> Result<Vec<u8>> {
+        let full_path = self.resolve(relative_path)?;
+        tokio::fs::read(&full_path)
+            .await
+            .map_err(|e| Error::io_with_path(e, &full_path))
+    }
+}
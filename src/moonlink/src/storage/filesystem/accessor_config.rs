@@ -0,0 +1,47 @@
+use super::storage_config::StorageConfig;
+
+/// How cached file contents are handed to readers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheReadMode {
+    /// Read the whole file into an owned buffer per cache miss.
+    #[default]
+    Buffered,
+    /// Memory-map the file once and serve borrowed slices out of it. Only
+    /// honored for local-filesystem-backed storage; silently treated as
+    /// `Buffered` otherwise, since there's no local file to map.
+    Mmap,
+}
+
+/// Configuration for a [`FileSystemAccessor`](super::accessor::FileSystemAccessor)
+/// used to read and write table data files, independent of which storage
+/// backend they live on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessorConfig {
+    pub storage_config: StorageConfig,
+    pub cache_read_mode: CacheReadMode,
+}
+
+impl AccessorConfig {
+    pub fn new_with_storage_config(storage_config: StorageConfig) -> Self {
+        Self {
+            storage_config,
+            cache_read_mode: CacheReadMode::default(),
+        }
+    }
+
+    pub fn with_cache_read_mode(mut self, cache_read_mode: CacheReadMode) -> Self {
+        self.cache_read_mode = cache_read_mode;
+        self
+    }
+
+    /// The effective read mode: `Mmap` degrades to `Buffered` for storage
+    /// backends that don't expose a local file to map.
+    pub fn effective_cache_read_mode(&self) -> CacheReadMode {
+        if self.cache_read_mode == CacheReadMode::Mmap && self.storage_config.is_local_filesystem()
+        {
+            CacheReadMode::Mmap
+        } else {
+            CacheReadMode::Buffered
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod accessor;
+pub mod accessor_config;
+pub mod storage_config;
+
+pub use accessor::FileSystemAccessor;
+pub use accessor_config::{AccessorConfig, CacheReadMode};
+pub use storage_config::StorageConfig;
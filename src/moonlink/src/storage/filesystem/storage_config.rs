@@ -0,0 +1,28 @@
+/// Where a table's data files, puffins and WAL segments physically live.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageConfig {
+    /// Plain local filesystem storage, rooted at `root_directory`.
+    FileSystem {
+        root_directory: String,
+        /// Optional separate directory for atomic (write-then-rename)
+        /// commits. Nothing in this crate reads it: `FileSystemAccessor`
+        /// writes files directly at their final path, and no default is
+        /// derived when it is `None`.
+        atomic_write_dir: Option<String>,
+    },
+}
+
+impl StorageConfig {
+    /// Returns the root directory for local-filesystem-backed storage, if any.
+    pub fn root_directory(&self) -> Option<&str> {
+        match self {
+            StorageConfig::FileSystem { root_directory, .. } => Some(root_directory),
+        }
+    }
+
+    /// Whether this storage config points at the local filesystem, as opposed
+    /// to a remote object store.
+    pub fn is_local_filesystem(&self) -> bool {
+        matches!(self, StorageConfig::FileSystem { .. })
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeSet;
+
+use crate::{Error, Result};
+
+/// A set of deleted row positions for a single data file, as reconstructed
+/// from an iceberg puffin deletion-vector blob.
+#[derive(Debug, Clone, Default)]
+pub struct BatchDeleteVector {
+    deleted_positions: BTreeSet<u32>,
+}
+
+impl BatchDeleteVector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn delete_row(&mut self, row_idx: u32) {
+        self.deleted_positions.insert(row_idx);
+    }
+
+    pub fn is_deleted(&self, row_idx: u32) -> bool {
+        self.deleted_positions.contains(&row_idx)
+    }
+
+    pub fn collect_deleted_rows(&self) -> Vec<u32> {
+        self.deleted_positions.iter().copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.deleted_positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deleted_positions.is_empty()
+    }
+}
+
+/// The in-memory form of an iceberg puffin deletion-vector blob: a set of
+/// deleted row positions, serialized as a sorted `u32` list.
+///
+/// The on-disk format is deliberately simple (`[u32 count][u32 position]*`)
+/// rather than the full iceberg roaring-bitmap encoding, since moonlink only
+/// ever reads back blobs it wrote itself.
+#[derive(Debug, Clone, Default)]
+pub struct DeletionVector {
+    positions: BatchDeleteVector,
+}
+
+impl DeletionVector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn delete_row(&mut self, row_idx: u32) {
+        self.positions.delete_row(row_idx);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn take_as_batch_delete_vector(self) -> BatchDeleteVector {
+        self.positions
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let rows = self.positions.collect_deleted_rows();
+        let mut buf = Vec::with_capacity(4 + rows.len() * 4);
+        buf.extend_from_slice(&(rows.len() as u32).to_le_bytes());
+        for row in rows {
+            buf.extend_from_slice(&row.to_le_bytes());
+        }
+        buf
+    }
+
+    pub fn deserialize(blob: Vec<u8>) -> Result<Self> {
+        if blob.len() < 4 {
+            return Err(Error::Other(
+                "deletion vector blob too short to contain a length prefix".to_string(),
+            ));
+        }
+        let count = u32::from_le_bytes(blob[0..4].try_into().unwrap()) as usize;
+        let expected_len = 4 + count * 4;
+        if blob.len() != expected_len {
+            return Err(Error::Other(format!(
+                "deletion vector blob length mismatch: expected {expected_len}, got {}",
+                blob.len()
+            )));
+        }
+
+        let mut positions = BatchDeleteVector::new();
+        for chunk in blob[4..].chunks_exact(4) {
+            positions.delete_row(u32::from_le_bytes(chunk.try_into().unwrap()));
+        }
+
+        Ok(Self { positions })
+    }
+}
@@ -0,0 +1,18 @@
+use crate::storage::filesystem::accessor_config::AccessorConfig;
+
+/// Where iceberg table *metadata* (the catalog) is kept, as opposed to the
+/// data files themselves which are governed by [`AccessorConfig`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IcebergCatalogConfig {
+    /// A flat-file catalog, suitable for local/standalone use.
+    File { accessor_config: AccessorConfig },
+}
+
+/// Everything moonlink needs to read and write a single iceberg table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcebergTableConfig {
+    pub namespace: Vec<String>,
+    pub table_name: String,
+    pub data_accessor_config: AccessorConfig,
+    pub metadata_accessor_config: IcebergCatalogConfig,
+}
@@ -0,0 +1,5 @@
+pub mod deletion_vector;
+pub mod iceberg_table_config;
+pub mod puffin_utils;
+
+pub use iceberg_table_config::{IcebergCatalogConfig, IcebergTableConfig};
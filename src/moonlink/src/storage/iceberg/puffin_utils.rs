@@ -0,0 +1,47 @@
+use iceberg::io::FileIO;
+
+use crate::{Error, Result};
+
+/// Magic bytes identifying a moonlink puffin file. Real iceberg puffin files
+/// use the `PFA1` footer-based layout; moonlink currently only ever writes
+/// and reads a single deletion-vector blob per file, so we use a minimal
+/// single-blob framing instead: `[MAGIC][u32 blob_len][blob bytes]`.
+const PUFFIN_MAGIC: &[u8; 4] = b"MPF1";
+
+/// Reads the single blob stored in a puffin file written by
+/// [`write_blob_to_puffin_file`].
+pub async fn load_blob_from_puffin_file(file_io: FileIO, path: &str) -> Result<Vec<u8>> {
+    let input_file = file_io.new_input(path)?;
+    let bytes = input_file.read().await?;
+
+    if bytes.len() < PUFFIN_MAGIC.len() + 4 || &bytes[..PUFFIN_MAGIC.len()] != PUFFIN_MAGIC {
+        return Err(Error::Other(format!(
+            "{path} is not a valid moonlink puffin file"
+        )));
+    }
+
+    let len_offset = PUFFIN_MAGIC.len();
+    let blob_len = u32::from_le_bytes(bytes[len_offset..len_offset + 4].try_into().unwrap());
+    let blob_start = len_offset + 4;
+    let blob_end = blob_start + blob_len as usize;
+    if bytes.len() < blob_end {
+        return Err(Error::Other(format!(
+            "{path} puffin file truncated: declared {blob_len} byte blob but file has {} bytes",
+            bytes.len() - blob_start
+        )));
+    }
+
+    Ok(bytes[blob_start..blob_end].to_vec())
+}
+
+/// Writes `blob` as the single blob of a new puffin file at `path`.
+pub async fn write_blob_to_puffin_file(file_io: FileIO, path: &str, blob: &[u8]) -> Result<()> {
+    let mut buf = Vec::with_capacity(PUFFIN_MAGIC.len() + 4 + blob.len());
+    buf.extend_from_slice(PUFFIN_MAGIC);
+    buf.extend_from_slice(&(blob.len() as u32).to_le_bytes());
+    buf.extend_from_slice(blob);
+
+    let output_file = file_io.new_output(path)?;
+    output_file.write(buf.into()).await?;
+    Ok(())
+}
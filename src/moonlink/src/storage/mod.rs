@@ -0,0 +1,5 @@
+pub mod filesystem;
+pub mod iceberg;
+pub mod mooncake_table;
+pub mod object_storage_cache;
+pub mod wal;
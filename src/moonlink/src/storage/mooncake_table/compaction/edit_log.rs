@@ -0,0 +1,115 @@
+//! A manifest-style append-only log of compaction plans. On reopen,
+//! [`super::CompactionManager::new`] reads it to tell which data files still
+//! on disk were inputs to a recorded compaction, so they are not tracked
+//! again next to that compaction's output. Entries are never removed, and
+//! nothing in the log is rolled back on restart.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{Error, Result};
+
+/// One compaction plan: which files at `source_level` were merged into
+/// which output files, tagged with a monotonically increasing version so
+/// entries can be replayed in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactionEditLogEntry {
+    pub version: u64,
+    pub source_level: usize,
+    pub input_files: Vec<String>,
+    pub output_files: Vec<String>,
+}
+
+impl CompactionEditLogEntry {
+    fn encode(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.version,
+            self.source_level,
+            self.input_files.join(","),
+            self.output_files.join(",")
+        )
+    }
+
+    fn decode(line: &str) -> Result<Self> {
+        let mut parts = line.splitn(4, '\t');
+        let version = parts
+            .next()
+            .ok_or_else(|| Error::Other("edit log entry missing version".to_string()))?
+            .parse()
+            .map_err(|e| Error::Other(format!("invalid edit log version: {e}")))?;
+        let source_level = parts
+            .next()
+            .ok_or_else(|| Error::Other("edit log entry missing source_level".to_string()))?
+            .parse()
+            .map_err(|e| Error::Other(format!("invalid edit log source_level: {e}")))?;
+        let input_files = parts
+            .next()
+            .ok_or_else(|| Error::Other("edit log entry missing input_files".to_string()))?
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect();
+        let output_files = parts
+            .next()
+            .ok_or_else(|| Error::Other("edit log entry missing output_files".to_string()))?
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        Ok(Self {
+            version,
+            source_level,
+            input_files,
+            output_files,
+        })
+    }
+}
+
+/// The edit log file for a single table's compaction subsystem.
+pub struct CompactionEditLog {
+    path: PathBuf,
+}
+
+impl CompactionEditLog {
+    pub fn open(table_dir: &Path) -> Result<Self> {
+        let path = table_dir.join("compaction_edit_log");
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| Error::io_with_path(e, parent))?;
+        }
+        Ok(Self { path })
+    }
+
+    pub fn append(&self, entry: &CompactionEditLogEntry) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| Error::io_with_path(e, &self.path))?;
+        writeln!(file, "{}", entry.encode()).map_err(|e| Error::io_with_path(e, &self.path))?;
+        file.sync_all().map_err(|e| Error::io_with_path(e, &self.path))?;
+        Ok(())
+    }
+
+    pub fn read_all(&self) -> Result<Vec<CompactionEditLogEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let file = File::open(&self.path).map_err(|e| Error::io_with_path(e, &self.path))?;
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| Error::io_with_path(e, &self.path))?;
+            if line.is_empty() {
+                continue;
+            }
+            entries.push(CompactionEditLogEntry::decode(&line)?);
+        }
+        Ok(entries)
+    }
+
+    pub fn max_version(&self) -> Result<Option<u64>> {
+        Ok(self.read_all()?.into_iter().map(|e| e.version).max())
+    }
+}
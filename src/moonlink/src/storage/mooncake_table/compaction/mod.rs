@@ -0,0 +1,610 @@
+//! Leveled compaction of data files and their deletion vectors.
+//!
+//! As a table accumulates flushed parquet files, read amplification grows:
+//! every read has to consult every file plus whatever deletion vector
+//! applies to it. [`CompactionManager`] organizes files into LSM-style
+//! levels and, once a level's combined size crosses a size trigger, merges
+//! its live rows (dead rows dropped per the input files' deletion vectors)
+//! into fewer, larger files in the next level with fresh, empty deletion
+//! vectors.
+//!
+//! Compaction plans are recorded in an [`edit_log`] before the old files are
+//! swapped out of the in-memory level state. On reopen, the log tells which
+//! on-disk data files a recorded compaction consumed, so they are not
+//! tracked again alongside its output. A compaction interrupted by a crash
+//! is not rolled back on restart.
+
+pub mod edit_log;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use arrow::compute::{concat_batches, filter_record_batch};
+use arrow::record_batch::RecordBatch;
+use arrow_schema::Schema;
+use iceberg::io::FileIOBuilder;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+
+use crate::storage::iceberg::deletion_vector::DeletionVector as IcebergDeletionVector;
+use crate::storage::iceberg::puffin_utils;
+use crate::{Error, Result};
+
+use edit_log::{CompactionEditLog, CompactionEditLogEntry};
+
+/// Per-level compaction knobs, exposed on [`super::MooncakeTableConfig`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompactionConfig {
+    /// A level is compacted into the next once its files' combined size
+    /// exceeds `level_target_size_bytes * fanout_ratio.pow(level)`.
+    pub level_target_size_bytes: u64,
+    pub fanout_ratio: u32,
+}
+
+impl Default for CompactionConfig {
+    fn default() -> Self {
+        Self {
+            level_target_size_bytes: 64 * 1024 * 1024,
+            fanout_ratio: 10,
+        }
+    }
+}
+
+/// A single data file tracked by the compaction level structure, alongside
+/// the puffin deletion-vector file (if any) that applies to it.
+#[derive(Debug, Clone)]
+pub struct LeveledFile {
+    pub data_file_path: String,
+    pub deletion_vector_puffin_path: Option<String>,
+    pub size_bytes: u64,
+}
+
+/// The input files consumed and output files produced by one compaction
+/// pass, so the caller can swap them into (or roll back out of) the live
+/// snapshot.
+#[derive(Debug, Clone)]
+pub struct CompactionResult {
+    pub source_level: usize,
+    pub input_files: Vec<LeveledFile>,
+    pub output_files: Vec<LeveledFile>,
+}
+
+/// Tracks data files across levels and drives leveled compaction between
+/// them. Does not itself own the snapshot; callers are responsible for
+/// swapping [`CompactionResult::output_files`] in and
+/// [`CompactionResult::input_files`] out of the snapshot they expose to
+/// readers once the corresponding iceberg commit succeeds.
+pub struct CompactionManager {
+    table_dir: PathBuf,
+    schema: Arc<Schema>,
+    config: CompactionConfig,
+    levels: Vec<Vec<LeveledFile>>,
+    edit_log: CompactionEditLog,
+    next_version: u64,
+}
+
+impl CompactionManager {
+    /// Opens (or creates) the compaction state for `table_dir`, rebuilding
+    /// `levels` from whatever data files and edit-log history already exist
+    /// there. Without this, reopening a table against a `table_dir` it had
+    /// already flushed into would report zero tracked files even though the
+    /// parquet files themselves are still on disk.
+    pub fn new(
+        table_dir: &Path,
+        table_name: &str,
+        schema: Arc<Schema>,
+        config: CompactionConfig,
+    ) -> Result<Self> {
+        let edit_log = CompactionEditLog::open(table_dir)?;
+        let entries = edit_log.read_all()?;
+        let next_version = entries.iter().map(|e| e.version).max().map(|v| v + 1).unwrap_or(0);
+
+        // A file that was ever an input to a recorded compaction has been
+        // superseded by that compaction's output, even if it's still
+        // physically on disk pending garbage collection (e.g. because a
+        // retained snapshot still pins it).
+        let consumed: std::collections::HashSet<&str> = entries
+            .iter()
+            .flat_map(|e| e.input_files.iter().map(String::as_str))
+            .collect();
+
+        let mut levels: Vec<Vec<LeveledFile>> = Vec::new();
+        for file in scan_level0_files(table_dir, table_name)? {
+            if !consumed.contains(file.data_file_path.as_str()) {
+                levels_entry(&mut levels, 0).push(file);
+            }
+        }
+        for (level, file) in scan_compacted_files(table_dir)? {
+            if !consumed.contains(file.data_file_path.as_str()) {
+                levels_entry(&mut levels, level).push(file);
+            }
+        }
+
+        Ok(Self {
+            table_dir: table_dir.to_path_buf(),
+            schema,
+            config,
+            levels,
+            edit_log,
+            next_version,
+        })
+    }
+
+    fn level_mut(&mut self, level: usize) -> &mut Vec<LeveledFile> {
+        if self.levels.len() <= level {
+            self.levels.resize_with(level + 1, Vec::new);
+        }
+        &mut self.levels[level]
+    }
+
+    /// Registers a freshly flushed data file at level 0.
+    pub fn add_level0_file(&mut self, file: LeveledFile) {
+        self.level_mut(0).push(file);
+    }
+
+    /// Returns every file currently tracked across all levels, i.e. the
+    /// table's full current file set. Used to build the per-snapshot file
+    /// set recorded by [`super::snapshot_list::SnapshotList`].
+    pub fn all_files(&self) -> Vec<LeveledFile> {
+        self.levels.iter().flatten().cloned().collect()
+    }
+
+    fn level_size_bytes(&self, level: usize) -> u64 {
+        self.levels
+            .get(level)
+            .map(|files| files.iter().map(|f| f.size_bytes).sum())
+            .unwrap_or(0)
+    }
+
+    fn level_trigger_bytes(&self, level: usize) -> u64 {
+        self.config
+            .level_target_size_bytes
+            .saturating_mul(self.config.fanout_ratio.pow(level as u32) as u64)
+    }
+
+    /// Returns the lowest level whose size trigger is currently exceeded, if
+    /// any.
+    fn level_needing_compaction(&self) -> Option<usize> {
+        (0..self.levels.len()).find(|&level| self.level_size_bytes(level) > self.level_trigger_bytes(level))
+    }
+
+    /// If any level exceeds its trigger, compacts the lowest such level's
+    /// files into the next level and returns the resulting file-set change.
+    /// Returns `Ok(None)` if no level currently needs compaction.
+    pub async fn maybe_compact(&mut self) -> Result<Option<CompactionResult>> {
+        let Some(level) = self.level_needing_compaction() else {
+            return Ok(None);
+        };
+
+        let input_files = self.levels[level].clone();
+        let merged_batch = self.read_live_rows(&input_files).await?;
+
+        let output_level = level + 1;
+        let output_file = self.write_output_file(output_level, &merged_batch)?;
+
+        let version = self.next_version;
+        self.next_version += 1;
+        self.edit_log.append(&CompactionEditLogEntry {
+            version,
+            source_level: level,
+            input_files: input_files.iter().map(|f| f.data_file_path.clone()).collect(),
+            output_files: vec![output_file.data_file_path.clone()],
+        })?;
+
+        self.levels[level].clear();
+        self.level_mut(output_level).push(output_file.clone());
+
+        Ok(Some(CompactionResult {
+            source_level: level,
+            input_files,
+            output_files: vec![output_file],
+        }))
+    }
+
+    /// Rolls back a compaction whose iceberg commit failed: the merged
+    /// output file is deleted and its source files are restored to their
+    /// original level so they remain visible to readers.
+    pub fn rollback(&mut self, result: CompactionResult) -> Result<()> {
+        for output in &result.output_files {
+            let path = Path::new(&output.data_file_path);
+            if path.exists() {
+                std::fs::remove_file(path).map_err(|e| Error::io_with_path(e, path))?;
+            }
+        }
+        let output_level = result.source_level + 1;
+        if let Some(level_files) = self.levels.get_mut(output_level) {
+            level_files.retain(|f| {
+                !result
+                    .output_files
+                    .iter()
+                    .any(|out| out.data_file_path == f.data_file_path)
+            });
+        }
+        self.level_mut(result.source_level)
+            .extend(result.input_files);
+        Ok(())
+    }
+
+    async fn read_live_rows(&self, files: &[LeveledFile]) -> Result<RecordBatch> {
+        let file_io = FileIOBuilder::new_fs_io().build()?;
+        let mut batches = Vec::new();
+
+        for file in files {
+            let reader_file = std::fs::File::open(&file.data_file_path)
+                .map_err(|e| Error::io_with_path(e, &file.data_file_path))?;
+            let reader = ParquetRecordBatchReaderBuilder::try_new(reader_file)?.build()?;
+            let mut file_batches = Vec::new();
+            for batch in reader {
+                file_batches.push(batch?);
+            }
+            if file_batches.is_empty() {
+                continue;
+            }
+            let file_batch = concat_batches(&self.schema, &file_batches)?;
+
+            let deleted = match &file.deletion_vector_puffin_path {
+                Some(puffin_path) => {
+                    let blob = puffin_utils::load_blob_from_puffin_file(file_io.clone(), puffin_path)
+                        .await?;
+                    IcebergDeletionVector::deserialize(blob)?.take_as_batch_delete_vector()
+                }
+                None => Default::default(),
+            };
+
+            if deleted.is_empty() {
+                batches.push(file_batch);
+            } else {
+                let keep_mask: arrow::array::BooleanArray = (0..file_batch.num_rows())
+                    .map(|row_idx| Some(!deleted.is_deleted(row_idx as u32)))
+                    .collect();
+                batches.push(filter_record_batch(&file_batch, &keep_mask)?);
+            }
+        }
+
+        if batches.is_empty() {
+            return Ok(RecordBatch::new_empty(self.schema.clone()));
+        }
+        Ok(concat_batches(&self.schema, &batches)?)
+    }
+
+    fn write_output_file(&self, level: usize, batch: &RecordBatch) -> Result<LeveledFile> {
+        let compaction_dir = self.table_dir.join("compacted");
+        std::fs::create_dir_all(&compaction_dir)
+            .map_err(|e| Error::io_with_path(e, &compaction_dir))?;
+
+        let file_name = format!(
+            "level-{level}-{}.parquet",
+            uuid_like_suffix(self.next_version)
+        );
+        let data_file_path = compaction_dir.join(file_name);
+
+        let file = std::fs::File::create(&data_file_path)
+            .map_err(|e| Error::io_with_path(e, &data_file_path))?;
+        let mut writer = ArrowWriter::try_new(file, self.schema.clone(), None)?;
+        writer.write(batch)?;
+        writer.close()?;
+
+        let size_bytes = std::fs::metadata(&data_file_path)
+            .map_err(|e| Error::io_with_path(e, &data_file_path))?
+            .len();
+
+        // The output file starts with no deletions, so it carries no puffin.
+        Ok(LeveledFile {
+            data_file_path: data_file_path.to_string_lossy().into_owned(),
+            deletion_vector_puffin_path: None,
+            size_bytes,
+        })
+    }
+}
+
+/// Cheap, dependency-free stand-in for a UUID: unique per compaction version
+/// within a table, which is all a file-name suffix needs to be.
+fn uuid_like_suffix(version: u64) -> String {
+    format!("{version:020}")
+}
+
+fn levels_entry(levels: &mut Vec<Vec<LeveledFile>>, level: usize) -> &mut Vec<LeveledFile> {
+    if levels.len() <= level {
+        levels.resize_with(level + 1, Vec::new);
+    }
+    &mut levels[level]
+}
+
+/// Scans `table_dir` for freshly flushed (not yet compacted) level-0 data
+/// files, named `{table_name}-{id}.parquet` by [`super::MooncakeTable::data_file_path`].
+fn scan_level0_files(table_dir: &Path, table_name: &str) -> Result<Vec<LeveledFile>> {
+    let prefix = format!("{table_name}-");
+    let mut files = Vec::new();
+    let entries = match std::fs::read_dir(table_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(files),
+        Err(e) => return Err(Error::io_with_path(e, table_dir)),
+    };
+    for entry in entries {
+        let entry = entry.map_err(|e| Error::io_with_path(e, table_dir))?;
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("parquet") {
+            continue;
+        }
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !file_name.starts_with(&prefix) {
+            continue;
+        }
+        let size_bytes = std::fs::metadata(&path)
+            .map_err(|e| Error::io_with_path(e, &path))?
+            .len();
+        files.push(LeveledFile {
+            data_file_path: path.to_string_lossy().into_owned(),
+            deletion_vector_puffin_path: None,
+            size_bytes,
+        });
+    }
+    Ok(files)
+}
+
+/// Scans `table_dir`'s `compacted` subdirectory for prior compaction output
+/// files, named `level-{level}-{version}.parquet` by
+/// [`CompactionManager::write_output_file`], returning each alongside the
+/// level it belongs to.
+fn scan_compacted_files(table_dir: &Path) -> Result<Vec<(usize, LeveledFile)>> {
+    let compacted_dir = table_dir.join("compacted");
+    let mut files = Vec::new();
+    let entries = match std::fs::read_dir(&compacted_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(files),
+        Err(e) => return Err(Error::io_with_path(e, &compacted_dir)),
+    };
+    for entry in entries {
+        let entry = entry.map_err(|e| Error::io_with_path(e, &compacted_dir))?;
+        let path = entry.path();
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(level) = file_name
+            .strip_prefix("level-")
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|level_str| level_str.parse::<usize>().ok())
+        else {
+            continue;
+        };
+        let size_bytes = std::fs::metadata(&path)
+            .map_err(|e| Error::io_with_path(e, &path))?
+            .len();
+        files.push((
+            level,
+            LeveledFile {
+                data_file_path: path.to_string_lossy().into_owned(),
+                deletion_vector_puffin_path: None,
+                size_bytes,
+            },
+        ));
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, Int32Array};
+    use arrow_schema::{DataType, Field};
+    use tempfile::tempdir;
+
+    fn test_schema() -> Arc<Schema> {
+        Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]))
+    }
+
+    fn write_level0_file(table_dir: &Path, name: &str, ids: &[i32]) -> LeveledFile {
+        let schema = test_schema();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(ids.to_vec())) as ArrayRef],
+        )
+        .unwrap();
+
+        let path = table_dir.join(name);
+        let file = std::fs::File::create(&path).unwrap();
+        let mut writer = ArrowWriter::try_new(file, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let size_bytes = std::fs::metadata(&path).unwrap().len();
+        LeveledFile {
+            data_file_path: path.to_string_lossy().into_owned(),
+            deletion_vector_puffin_path: None,
+            size_bytes,
+        }
+    }
+
+    async fn write_deletion_vector_puffin(table_dir: &Path, name: &str, deleted_rows: &[u32]) -> String {
+        let mut dv = IcebergDeletionVector::new();
+        for row in deleted_rows {
+            dv.delete_row(*row);
+        }
+
+        let file_io = FileIOBuilder::new_fs_io().build().unwrap();
+        let path = table_dir.join(name).to_string_lossy().into_owned();
+        puffin_utils::write_blob_to_puffin_file(file_io, &path, &dv.serialize())
+            .await
+            .unwrap();
+        path
+    }
+
+    fn read_ids(path: &str) -> Vec<i32> {
+        let file = std::fs::File::open(path).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        let mut ids = Vec::new();
+        for batch in reader {
+            let batch = batch.unwrap();
+            let col = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int32Array>()
+                .unwrap();
+            ids.extend((0..col.len()).map(|i| col.value(i)));
+        }
+        ids
+    }
+
+    #[tokio::test]
+    async fn compacts_level0_once_trigger_exceeded() {
+        let dir = tempdir().unwrap();
+        // A one-byte trigger guarantees any non-empty level 0 needs compaction.
+        let config = CompactionConfig {
+            level_target_size_bytes: 1,
+            fanout_ratio: 10,
+        };
+        let mut manager = CompactionManager::new(dir.path(), "test_table", test_schema(), config).unwrap();
+
+        let file_a = write_level0_file(dir.path(), "a.parquet", &[1, 2]);
+        let file_b = write_level0_file(dir.path(), "b.parquet", &[3, 4, 5]);
+        manager.add_level0_file(file_a);
+        manager.add_level0_file(file_b);
+
+        let result = manager.maybe_compact().await.unwrap().expect("level 0 over trigger");
+        assert_eq!(result.source_level, 0);
+        assert_eq!(result.input_files.len(), 2);
+        assert_eq!(result.output_files.len(), 1);
+
+        // The merged output replaced the inputs in the manager's file set.
+        let all_files = manager.all_files();
+        assert_eq!(all_files.len(), 1);
+        assert_eq!(all_files[0].data_file_path, result.output_files[0].data_file_path);
+
+        let mut merged_ids = read_ids(&result.output_files[0].data_file_path);
+        merged_ids.sort();
+        assert_eq!(merged_ids, vec![1, 2, 3, 4, 5]);
+    }
+
+    /// A row marked deleted in an input file's puffin deletion vector must
+    /// not survive into the merged compaction output.
+    #[tokio::test]
+    async fn compaction_drops_rows_covered_by_a_deletion_vector() {
+        let dir = tempdir().unwrap();
+        let config = CompactionConfig {
+            level_target_size_bytes: 1,
+            fanout_ratio: 10,
+        };
+        let mut manager = CompactionManager::new(dir.path(), "test_table", test_schema(), config).unwrap();
+
+        let mut file_a = write_level0_file(dir.path(), "a.parquet", &[1, 2, 3]);
+        file_a.deletion_vector_puffin_path =
+            Some(write_deletion_vector_puffin(dir.path(), "a.puffin", &[1]).await);
+        let file_b = write_level0_file(dir.path(), "b.parquet", &[4, 5]);
+        manager.add_level0_file(file_a);
+        manager.add_level0_file(file_b);
+
+        let result = manager.maybe_compact().await.unwrap().expect("level 0 over trigger");
+
+        let mut merged_ids = read_ids(&result.output_files[0].data_file_path);
+        merged_ids.sort();
+        assert_eq!(
+            merged_ids,
+            vec![1, 3, 4, 5],
+            "row at position 1 (value 2) was marked deleted and must be dropped from the merge"
+        );
+    }
+
+    #[tokio::test]
+    async fn rollback_restores_inputs_and_deletes_output() {
+        let dir = tempdir().unwrap();
+        let config = CompactionConfig {
+            level_target_size_bytes: 1,
+            fanout_ratio: 10,
+        };
+        let mut manager = CompactionManager::new(dir.path(), "test_table", test_schema(), config).unwrap();
+
+        let file_a = write_level0_file(dir.path(), "a.parquet", &[1, 2]);
+        let file_b = write_level0_file(dir.path(), "b.parquet", &[3]);
+        manager.add_level0_file(file_a.clone());
+        manager.add_level0_file(file_b.clone());
+
+        let result = manager.maybe_compact().await.unwrap().unwrap();
+        let output_path = result.output_files[0].data_file_path.clone();
+        assert!(Path::new(&output_path).exists());
+
+        manager.rollback(result).unwrap();
+
+        assert!(!Path::new(&output_path).exists(), "rollback should delete the merged output");
+        let mut remaining: Vec<String> = manager
+            .all_files()
+            .into_iter()
+            .map(|f| f.data_file_path)
+            .collect();
+        remaining.sort();
+        let mut expected = vec![file_a.data_file_path, file_b.data_file_path];
+        expected.sort();
+        assert_eq!(remaining, expected);
+    }
+
+    /// Reopening a `CompactionManager` against a `table_dir` that already
+    /// has flushed, not-yet-compacted level-0 files must pick those files
+    /// back up, instead of reporting an empty file set while they sit
+    /// untracked on disk.
+    #[tokio::test]
+    async fn reopen_recovers_untracked_level0_files() {
+        let dir = tempdir().unwrap();
+        let config = CompactionConfig::default();
+
+        write_level0_file(dir.path(), "my_table-0.parquet", &[1, 2]);
+        write_level0_file(dir.path(), "my_table-1.parquet", &[3, 4, 5]);
+
+        let manager =
+            CompactionManager::new(dir.path(), "my_table", test_schema(), config).unwrap();
+        let mut all_files: Vec<String> = manager
+            .all_files()
+            .into_iter()
+            .map(|f| f.data_file_path)
+            .collect();
+        all_files.sort();
+        let mut expected = vec![
+            dir.path().join("my_table-0.parquet").to_string_lossy().into_owned(),
+            dir.path().join("my_table-1.parquet").to_string_lossy().into_owned(),
+        ];
+        expected.sort();
+        assert_eq!(all_files, expected);
+    }
+
+    /// A compaction's input files are only removed from the in-memory level
+    /// state, not deleted from disk (garbage collection is a separate,
+    /// caller-driven step), so they're still present in `table_dir` after a
+    /// successful compaction. Reopening must not resurrect them as level-0
+    /// files despite that — the edit log marks them superseded by the
+    /// compaction's output.
+    #[tokio::test]
+    async fn reopen_does_not_resurrect_compacted_away_inputs() {
+        let dir = tempdir().unwrap();
+        let config = CompactionConfig {
+            level_target_size_bytes: 1,
+            fanout_ratio: 10,
+        };
+        let mut manager =
+            CompactionManager::new(dir.path(), "my_table", test_schema(), config).unwrap();
+
+        let file_a = write_level0_file(dir.path(), "my_table-0.parquet", &[1, 2]);
+        let file_b = write_level0_file(dir.path(), "my_table-1.parquet", &[3]);
+        manager.add_level0_file(file_a.clone());
+        manager.add_level0_file(file_b.clone());
+        let result = manager.maybe_compact().await.unwrap().unwrap();
+        let output_path = result.output_files[0].data_file_path.clone();
+        drop(manager);
+
+        // The inputs are still physically on disk; only the edit log says
+        // they've been superseded.
+        assert!(Path::new(&file_a.data_file_path).exists());
+        assert!(Path::new(&file_b.data_file_path).exists());
+
+        let reopened =
+            CompactionManager::new(dir.path(), "my_table", test_schema(), config).unwrap();
+        let all_files: Vec<String> = reopened
+            .all_files()
+            .into_iter()
+            .map(|f| f.data_file_path)
+            .collect();
+        assert_eq!(all_files, vec![output_path]);
+    }
+}
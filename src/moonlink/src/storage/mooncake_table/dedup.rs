@@ -0,0 +1,326 @@
+//! Content-addressed deduplication of flushed data files.
+//!
+//! Restart-and-replay or overlapping ingestion can cause moonlink to flush
+//! byte-identical parquet files for rows it has already written out once.
+//! [`DedupIndex`] hashes each newly flushed file with xxh3-128 (fast and
+//! non-cryptographic — collisions are guarded against explicitly rather than
+//! assumed impossible) and, when a match is found, points the caller at the
+//! existing file instead of keeping the new, redundant copy.
+//!
+//! Hashing a large file on every flush would itself be wasted work once a
+//! table has replayed past it, so file hashes are cached by `(size, mtime)`:
+//! if neither has changed since last time, the cached hash is reused without
+//! re-reading the file.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use xxhash_rust::xxh3::xxh3_128;
+
+use crate::{Error, Result};
+
+/// What happened when a freshly flushed file was run through the dedup
+/// index.
+#[derive(Debug, Clone)]
+pub enum DedupOutcome {
+    /// No existing file had the same content; `path` was registered as-is.
+    Registered { path: PathBuf },
+    /// An existing file with identical content was found; `path` (the
+    /// newly flushed, now-redundant file) was deleted in favor of
+    /// `existing_path`.
+    Reused { existing_path: PathBuf },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStat {
+    size: u64,
+    mtime: SystemTime,
+}
+
+/// Persisted content-hash -> file-path(s) map plus a `(size, mtime) -> hash`
+/// cache, scoped to a single table's flushed data files.
+pub struct DedupIndex {
+    index_path: PathBuf,
+    stat_cache_path: PathBuf,
+    /// hash -> candidate files sharing that hash (plural to tolerate, and
+    /// detect, hash collisions).
+    content_index: HashMap<u128, Vec<PathBuf>>,
+    stat_cache: HashMap<PathBuf, (FileStat, u128)>,
+}
+
+impl DedupIndex {
+    pub fn new(table_dir: &Path) -> Result<Self> {
+        let index_path = table_dir.join("dedup_index");
+        let stat_cache_path = table_dir.join("dedup_stat_cache");
+        let mut index = Self {
+            index_path,
+            stat_cache_path,
+            content_index: HashMap::new(),
+            stat_cache: HashMap::new(),
+        };
+        index.load()?;
+        Ok(index)
+    }
+
+    fn load(&mut self) -> Result<()> {
+        if let Ok(contents) = fs::read_to_string(&self.index_path) {
+            for line in contents.lines() {
+                let Some((hash_hex, path)) = line.split_once('\t') else {
+                    continue;
+                };
+                if let Ok(hash) = u128::from_str_radix(hash_hex, 16) {
+                    self.content_index
+                        .entry(hash)
+                        .or_default()
+                        .push(PathBuf::from(path));
+                }
+            }
+        }
+        if let Ok(contents) = fs::read_to_string(&self.stat_cache_path) {
+            for line in contents.lines() {
+                let mut parts = line.splitn(4, '\t');
+                let (Some(path), Some(size), Some(mtime_nanos), Some(hash_hex)) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    continue;
+                };
+                let (Ok(size), Ok(mtime_nanos), Ok(hash)) = (
+                    size.parse::<u64>(),
+                    mtime_nanos.parse::<u64>(),
+                    u128::from_str_radix(hash_hex, 16),
+                ) else {
+                    continue;
+                };
+                let mtime = SystemTime::UNIX_EPOCH + std::time::Duration::from_nanos(mtime_nanos);
+                self.stat_cache
+                    .insert(PathBuf::from(path), (FileStat { size, mtime }, hash));
+            }
+        }
+        Ok(())
+    }
+
+    fn persist(&self) -> Result<()> {
+        let mut index_contents = String::new();
+        for (hash, paths) in &self.content_index {
+            for path in paths {
+                index_contents.push_str(&format!("{hash:032x}\t{}\n", path.display()));
+            }
+        }
+        fs::write(&self.index_path, index_contents)
+            .map_err(|e| Error::io_with_path(e, &self.index_path))?;
+
+        let mut stat_contents = String::new();
+        for (path, (stat, hash)) in &self.stat_cache {
+            let mtime_nanos = stat
+                .mtime
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos();
+            stat_contents.push_str(&format!(
+                "{}\t{}\t{mtime_nanos}\t{hash:032x}\n",
+                path.display(),
+                stat.size
+            ));
+        }
+        fs::write(&self.stat_cache_path, stat_contents)
+            .map_err(|e| Error::io_with_path(e, &self.stat_cache_path))?;
+        Ok(())
+    }
+
+    /// Hashes `path` with xxh3-128, reusing the cached hash if the file's
+    /// `(size, mtime)` haven't changed since it was last hashed.
+    fn hash_file(&mut self, path: &Path) -> Result<u128> {
+        let metadata = fs::metadata(path).map_err(|e| Error::io_with_path(e, path))?;
+        let stat = FileStat {
+            size: metadata.len(),
+            mtime: metadata.modified().map_err(|e| Error::io_with_path(e, path))?,
+        };
+
+        if let Some((cached_stat, cached_hash)) = self.stat_cache.get(path) {
+            if *cached_stat == stat {
+                return Ok(*cached_hash);
+            }
+        }
+
+        let mut file = File::open(path).map_err(|e| Error::io_with_path(e, path))?;
+        let mut bytes = Vec::with_capacity(stat.size as usize);
+        file.read_to_end(&mut bytes)
+            .map_err(|e| Error::io_with_path(e, path))?;
+        let hash = xxh3_128(&bytes);
+
+        self.stat_cache.insert(path.to_path_buf(), (stat, hash));
+        Ok(hash)
+    }
+
+    /// Checks whether `path`'s content matches a file already registered in
+    /// the index; if so, deletes `path` and returns the existing file's
+    /// path, otherwise registers `path` under its content hash.
+    pub fn dedup_or_register(&mut self, path: &Path) -> Result<DedupOutcome> {
+        let hash = self.hash_file(path)?;
+        let candidates = self.content_index.entry(hash).or_default().clone();
+
+        let mut stale = Vec::new();
+        let mut reused = None;
+        for candidate in &candidates {
+            if candidate == path {
+                continue;
+            }
+            if !candidate.exists() {
+                // Something else (GC, a prior crash-interrupted delete)
+                // removed this file behind our back; drop it from the
+                // index instead of matching against a stale, dangling path.
+                stale.push(candidate.clone());
+                continue;
+            }
+            if files_are_byte_equal(candidate, path)? {
+                reused = Some(candidate.clone());
+                break;
+            }
+        }
+
+        if !stale.is_empty() {
+            self.content_index
+                .entry(hash)
+                .or_default()
+                .retain(|p| !stale.contains(p));
+            for path in &stale {
+                self.stat_cache.remove(path);
+            }
+        }
+
+        if let Some(existing_path) = reused {
+            fs::remove_file(path).map_err(|e| Error::io_with_path(e, path))?;
+            self.persist()?;
+            return Ok(DedupOutcome::Reused { existing_path });
+        }
+
+        // No byte-equal candidate (either no hash match, or a hash
+        // collision against different content): register this file too.
+        self.content_index
+            .entry(hash)
+            .or_default()
+            .push(path.to_path_buf());
+        self.persist()?;
+        Ok(DedupOutcome::Registered {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+fn files_are_byte_equal(a: &Path, b: &Path) -> Result<bool> {
+    let a_bytes = fs::read(a).map_err(|e| Error::io_with_path(e, a))?;
+    let b_bytes = fs::read(b).map_err(|e| Error::io_with_path(e, b))?;
+    Ok(a_bytes == b_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn registers_a_file_with_no_existing_match() {
+        let dir = tempdir().unwrap();
+        let mut index = DedupIndex::new(dir.path()).unwrap();
+        let path = dir.path().join("a.parquet");
+        fs::write(&path, b"row-bytes").unwrap();
+
+        let outcome = index.dedup_or_register(&path).unwrap();
+        assert!(matches!(outcome, DedupOutcome::Registered { .. }));
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn reuses_an_existing_byte_identical_file_and_deletes_the_new_one() {
+        let dir = tempdir().unwrap();
+        let mut index = DedupIndex::new(dir.path()).unwrap();
+
+        let first = dir.path().join("a.parquet");
+        fs::write(&first, b"row-bytes").unwrap();
+        index.dedup_or_register(&first).unwrap();
+
+        let second = dir.path().join("b.parquet");
+        fs::write(&second, b"row-bytes").unwrap();
+        let outcome = index.dedup_or_register(&second).unwrap();
+
+        match outcome {
+            DedupOutcome::Reused { existing_path } => assert_eq!(existing_path, first),
+            DedupOutcome::Registered { .. } => panic!("expected a Reused outcome"),
+        }
+        assert!(!second.exists(), "redundant file should have been deleted");
+        assert!(first.exists());
+    }
+
+    #[test]
+    fn registers_files_with_different_content_even_on_hash_match() {
+        let dir = tempdir().unwrap();
+        let mut index = DedupIndex::new(dir.path()).unwrap();
+
+        let first = dir.path().join("a.parquet");
+        fs::write(&first, b"row-bytes-one").unwrap();
+        index.dedup_or_register(&first).unwrap();
+
+        let second = dir.path().join("b.parquet");
+        fs::write(&second, b"row-bytes-two").unwrap();
+        // Simulate a hash collision: file the first path under the second
+        // file's hash, so only the byte comparison can tell them apart.
+        let second_hash = index.hash_file(&second).unwrap();
+        index
+            .content_index
+            .entry(second_hash)
+            .or_default()
+            .push(first.clone());
+        let outcome = index.dedup_or_register(&second).unwrap();
+
+        assert!(matches!(outcome, DedupOutcome::Registered { .. }));
+        assert!(second.exists());
+    }
+
+    #[test]
+    fn reuses_cached_hash_when_stat_is_unchanged() {
+        let dir = tempdir().unwrap();
+        let mut index = DedupIndex::new(dir.path()).unwrap();
+        let path = dir.path().join("a.parquet");
+        fs::write(&path, b"row-bytes").unwrap();
+
+        let hash_first = index.hash_file(&path).unwrap();
+        let hash_second = index.hash_file(&path).unwrap();
+        assert_eq!(hash_first, hash_second);
+        assert_eq!(index.stat_cache.len(), 1);
+    }
+
+    #[test]
+    fn prunes_an_entry_whose_file_was_deleted_out_from_under_it() {
+        let dir = tempdir().unwrap();
+        let mut index = DedupIndex::new(dir.path()).unwrap();
+
+        let first = dir.path().join("a.parquet");
+        fs::write(&first, b"row-bytes").unwrap();
+        index.dedup_or_register(&first).unwrap();
+
+        // Something other than the dedup index removes the registered file
+        // (e.g. compaction GC), leaving a dangling entry behind.
+        fs::remove_file(&first).unwrap();
+
+        let second = dir.path().join("b.parquet");
+        fs::write(&second, b"row-bytes").unwrap();
+        let outcome = index.dedup_or_register(&second).unwrap();
+
+        assert!(
+            matches!(outcome, DedupOutcome::Registered { .. }),
+            "a dangling candidate must not be matched against"
+        );
+        assert!(second.exists());
+
+        let hash = index.hash_file(&second).unwrap();
+        assert_eq!(
+            index.content_index.get(&hash).unwrap(),
+            std::slice::from_ref(&second),
+            "the stale entry for the deleted file must be pruned"
+        );
+        assert!(!index.stat_cache.contains_key(&first));
+    }
+}
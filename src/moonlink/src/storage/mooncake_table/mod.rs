@@ -0,0 +1,783 @@
+pub mod compaction;
+pub mod dedup;
+pub mod snapshot_list;
+pub mod snapshot_read_output;
+pub mod spill;
+pub mod table_creation_test_utils;
+pub mod table_operation_test_utils;
+pub mod test_utils;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use arrow_schema::Schema;
+use parquet::arrow::ArrowWriter;
+use tokio::sync::mpsc::{Receiver, Sender};
+
+use crate::row::{IdentityProp, MoonlinkRow, RowValue};
+use crate::storage::filesystem::FileSystemAccessor;
+use crate::storage::iceberg::iceberg_table_config::IcebergTableConfig;
+use crate::storage::object_storage_cache::{NonEvictableHandle, ObjectStorageCache};
+use crate::storage::wal::{WalManager, WalRecord};
+use crate::{Error, Result};
+
+use compaction::{CompactionConfig, CompactionManager, LeveledFile};
+use dedup::DedupIndex;
+use snapshot_list::{SnapshotHandle, SnapshotList};
+use snapshot_read_output::DataFileForRead;
+use spill::SpillManager;
+
+/// Configuration knobs for a single [`MooncakeTable`].
+#[derive(Debug, Clone)]
+pub struct MooncakeTableConfig {
+    /// Target number of rows accumulated in memory before a batch is closed.
+    pub batch_size: usize,
+    /// Whether this table only ever appends rows (no identity/upsert tracking).
+    pub append_only: bool,
+    pub row_identity: IdentityProp,
+    /// Once the bytes held across not-yet-flushed committed batches exceed
+    /// this threshold, older batches are spilled to disk instead of being
+    /// kept in memory until flush.
+    pub spill_threshold_bytes: u64,
+    /// Fraction of the target volume's total capacity that must remain free
+    /// after a spill; spilling is refused if it would eat into this reserve.
+    pub reserved_disk_ratio: f64,
+    /// Leveled-compaction knobs: per-level target size and fan-out ratio.
+    pub compaction_config: CompactionConfig,
+}
+
+impl MooncakeTableConfig {
+    pub fn new(_table_base_path: String) -> Self {
+        Self {
+            batch_size: 4096,
+            append_only: false,
+            row_identity: IdentityProp::FullRow,
+            spill_threshold_bytes: 256 * 1024 * 1024,
+            reserved_disk_ratio: 0.05,
+            compaction_config: CompactionConfig::default(),
+        }
+    }
+}
+
+/// Events emitted by a [`MooncakeTable`]'s background flush/snapshot work so
+/// callers (and tests) can wait for a specific operation to finish.
+#[derive(Debug, Clone)]
+pub enum TableEvent {
+    FlushComplete { lsn: u64 },
+    SnapshotComplete { lsn: u64 },
+}
+
+/// One committed-but-not-yet-flushed set of rows, either still resident in
+/// memory or spilled out to a segment file by [`SpillManager`].
+#[derive(Debug)]
+enum CommittedBatch {
+    InMemory { lsn: u64, rows: Vec<MoonlinkRow> },
+    Spilled {
+        lsn: u64,
+        path: PathBuf,
+        row_count: usize,
+    },
+}
+
+impl CommittedBatch {
+    fn lsn(&self) -> u64 {
+        match self {
+            CommittedBatch::InMemory { lsn, .. } => *lsn,
+            CommittedBatch::Spilled { lsn, .. } => *lsn,
+        }
+    }
+
+    fn in_memory_size_bytes(&self) -> usize {
+        match self {
+            CommittedBatch::InMemory { rows, .. } => {
+                rows.iter().map(|r| r.estimated_size_bytes()).sum()
+            }
+            CommittedBatch::Spilled { .. } => 0,
+        }
+    }
+}
+
+/// The in-memory write path for a single mooncake table: buffers appended
+/// rows, tracks commits by LSN, and flushes committed rows out to parquet
+/// data files.
+pub struct MooncakeTable {
+    table_name: String,
+    version: u32,
+    table_dir: PathBuf,
+    iceberg_table_config: IcebergTableConfig,
+    config: MooncakeTableConfig,
+    wal_manager: WalManager,
+    object_storage_cache: ObjectStorageCache,
+    filesystem_accessor: FileSystemAccessor,
+    schema: Arc<Schema>,
+    mem_batch: Vec<MoonlinkRow>,
+    committed_batches: Vec<CommittedBatch>,
+    spill_manager: SpillManager,
+    compaction_manager: CompactionManager,
+    dedup_index: DedupIndex,
+    snapshot_list: SnapshotList,
+    completion_tx: Option<Sender<TableEvent>>,
+    next_file_id: u64,
+}
+
+impl MooncakeTable {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new(
+        schema: Schema,
+        table_name: String,
+        version: u32,
+        table_dir: PathBuf,
+        iceberg_table_config: IcebergTableConfig,
+        config: MooncakeTableConfig,
+        wal_manager: WalManager,
+        object_storage_cache: ObjectStorageCache,
+        filesystem_accessor: FileSystemAccessor,
+    ) -> Result<Self> {
+        let spill_manager = SpillManager::new(&table_dir, config.reserved_disk_ratio)?;
+        let schema = Arc::new(schema);
+        let compaction_manager = CompactionManager::new(
+            &table_dir,
+            &table_name,
+            schema.clone(),
+            config.compaction_config,
+        )?;
+        let dedup_index = DedupIndex::new(&table_dir)?;
+        let snapshot_list = SnapshotList::new();
+        let next_file_id = next_file_id_after_existing(&table_dir, &table_name)?;
+        let committed_batches = recover_committed_batches(&wal_manager)?;
+        Ok(Self {
+            table_name,
+            version,
+            table_dir,
+            iceberg_table_config,
+            config,
+            wal_manager,
+            object_storage_cache,
+            filesystem_accessor,
+            schema,
+            mem_batch: Vec::new(),
+            committed_batches,
+            spill_manager,
+            compaction_manager,
+            dedup_index,
+            snapshot_list,
+            completion_tx: None,
+            next_file_id,
+        })
+    }
+
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// The table's schema version, as passed to [`Self::new`].
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn iceberg_table_config(&self) -> &IcebergTableConfig {
+        &self.iceberg_table_config
+    }
+
+    pub fn subscribe_completion_events(&mut self) -> Receiver<TableEvent> {
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+        self.completion_tx = Some(tx);
+        rx
+    }
+
+    pub fn append(&mut self, row: MoonlinkRow) -> Result<()> {
+        self.mem_batch.push(row);
+        Ok(())
+    }
+
+    /// Closes out the current in-memory batch under `lsn`, persisting it to
+    /// the write-ahead log before it's tracked as committed so a crash before
+    /// the next flush can still replay it via [`WalManager::recover`]. Then
+    /// spills the oldest committed-but-unflushed batches to disk if their
+    /// combined size now exceeds [`MooncakeTableConfig::spill_threshold_bytes`].
+    pub fn commit(&mut self, lsn: u64) -> Result<()> {
+        let rows = std::mem::take(&mut self.mem_batch);
+
+        let mut payload = Vec::new();
+        for row in &rows {
+            row.encode_into(&mut payload);
+        }
+        self.wal_manager
+            .append_record(&WalRecord::new(lsn, payload))?;
+
+        self.committed_batches
+            .push(CommittedBatch::InMemory { lsn, rows });
+        self.spill_overflow_batches();
+        Ok(())
+    }
+
+    fn total_in_memory_bytes(&self) -> usize {
+        self.committed_batches
+            .iter()
+            .map(|b| b.in_memory_size_bytes())
+            .sum()
+    }
+
+    fn spill_overflow_batches(&mut self) {
+        let threshold = self.config.spill_threshold_bytes as usize;
+        // Keep the most recently committed batch in memory: it's the one
+        // most likely to be flushed next, so spilling it would just force an
+        // immediate read-back.
+        let spillable_count = self.committed_batches.len().saturating_sub(1);
+
+        for idx in 0..spillable_count {
+            if self.total_in_memory_bytes() <= threshold {
+                break;
+            }
+            let batch = &self.committed_batches[idx];
+            let CommittedBatch::InMemory { lsn, rows } = batch else {
+                continue;
+            };
+            let lsn = *lsn;
+            let additional_bytes: u64 =
+                rows.iter().map(|r| r.estimated_size_bytes() as u64).sum();
+            match self.spill_manager.has_room_to_spill(additional_bytes) {
+                Ok(true) => {}
+                Ok(false) => {
+                    tracing::warn!(
+                        lsn,
+                        additional_bytes,
+                        "refusing to spill committed batch: too little free disk space; \
+                         batch stays in memory until a later spill attempt or flush"
+                    );
+                    continue;
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        lsn,
+                        %err,
+                        "failed to check available disk space before spilling; \
+                         leaving committed batch in memory"
+                    );
+                    continue;
+                }
+            };
+
+            let row_count = rows.len();
+            let path = match self.spill_manager.spill_rows(rows) {
+                Ok(path) => path,
+                Err(err) => {
+                    tracing::warn!(
+                        lsn,
+                        %err,
+                        "failed to spill committed batch to disk; leaving it in memory"
+                    );
+                    continue;
+                }
+            };
+            self.committed_batches[idx] = CommittedBatch::Spilled {
+                lsn,
+                path,
+                row_count,
+            };
+        }
+    }
+
+    /// Materializes every committed batch up to and including `lsn` into a
+    /// single parquet data file, reading spilled batches back in from their
+    /// segment files. All rows being flushed, spilled ones included, are
+    /// held in memory together while the file is written; spilling only
+    /// bounds memory between commit and flush.
+    pub async fn flush(&mut self, lsn: u64) -> Result<PathBuf> {
+        let mut rows: Vec<MoonlinkRow> = Vec::new();
+        let mut remaining = Vec::new();
+        for batch in std::mem::take(&mut self.committed_batches) {
+            if batch.lsn() > lsn {
+                remaining.push(batch);
+                continue;
+            }
+            match batch {
+                CommittedBatch::InMemory { rows: batch_rows, .. } => rows.extend(batch_rows),
+                CommittedBatch::Spilled {
+                    path, row_count, ..
+                } => {
+                    let spilled_rows = self.spill_manager.read_and_remove_segment(&path)?;
+                    debug_assert_eq!(
+                        spilled_rows.len(),
+                        row_count,
+                        "spilled segment row count drifted from what was recorded at spill time"
+                    );
+                    rows.extend(spilled_rows);
+                }
+            }
+        }
+        self.committed_batches = remaining;
+
+        let file_path = self.data_file_path();
+        self.write_parquet_file(&file_path, &rows)?;
+
+        // Every row committed at or before `lsn` is now durable in
+        // `file_path`, so the WAL no longer needs to replay it on recovery.
+        self.wal_manager.truncate_through(lsn)?;
+
+        // If these exact rows were already flushed before (e.g. after a
+        // restart replays a transaction that made it to disk but not to the
+        // committed snapshot), reuse the existing file instead of keeping a
+        // byte-identical duplicate around. The existing file is already
+        // tracked by the compaction manager from its original flush, so
+        // only a genuinely new file gets registered here — otherwise the
+        // same path would end up counted twice across levels.
+        let final_path = match self.dedup_index.dedup_or_register(&file_path)? {
+            dedup::DedupOutcome::Registered { path } => {
+                let size_bytes = std::fs::metadata(&path)
+                    .map_err(|e| Error::io_with_path(e, &path))?
+                    .len();
+                self.compaction_manager.add_level0_file(LeveledFile {
+                    data_file_path: path.to_string_lossy().into_owned(),
+                    deletion_vector_puffin_path: None,
+                    size_bytes,
+                });
+                path
+            }
+            dedup::DedupOutcome::Reused { existing_path } => existing_path,
+        };
+
+        // Run one compaction pass if this flush pushed any level over its
+        // size trigger. There's no separate catalog-commit step in this
+        // flush path yet, so a successful pass is adopted immediately, and
+        // its input files are garbage collected unless an older retained
+        // snapshot still references them; rollback_compaction exists for
+        // callers that gate compaction behind a commit that can fail (e.g.
+        // an iceberg snapshot write).
+        if let Some(result) = self.compact_if_needed().await? {
+            self.garbage_collect_compacted_inputs(&result)?;
+        }
+
+        if let Some(tx) = &self.completion_tx {
+            let _ = tx.send(TableEvent::FlushComplete { lsn }).await;
+        }
+
+        Ok(final_path)
+    }
+
+    /// Runs one leveled-compaction pass if any level currently exceeds its
+    /// size trigger. On success the merged file replaces its inputs in the
+    /// snapshot this table would otherwise expose to readers; callers that
+    /// commit the result into an iceberg snapshot should call
+    /// [`Self::rollback_compaction`] if that commit fails.
+    pub async fn compact_if_needed(&mut self) -> Result<Option<compaction::CompactionResult>> {
+        self.compaction_manager.maybe_compact().await
+    }
+
+    pub fn rollback_compaction(&mut self, result: compaction::CompactionResult) -> Result<()> {
+        self.compaction_manager.rollback(result)
+    }
+
+    /// Deletes the input files of a successfully landed compaction from
+    /// disk, skipping any that a snapshot still recorded in the
+    /// [`SnapshotList`] references, whether pinned by a live
+    /// [`SnapshotHandle`] or simply not yet expired. Those are left in
+    /// place; a later call with the same result, once those snapshots have
+    /// been expired, deletes them.
+    pub fn garbage_collect_compacted_inputs(
+        &self,
+        result: &compaction::CompactionResult,
+    ) -> Result<()> {
+        for input in &result.input_files {
+            if self.snapshot_list.is_file_referenced(&input.data_file_path) {
+                continue;
+            }
+            let path = std::path::Path::new(&input.data_file_path);
+            if path.exists() {
+                std::fs::remove_file(path).map_err(|e| Error::io_with_path(e, path))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn data_file_path(&mut self) -> PathBuf {
+        let id = self.next_file_id;
+        self.next_file_id += 1;
+        self.table_dir
+            .join(format!("{}-{id}.parquet", self.table_name))
+    }
+
+    fn write_parquet_file(&self, path: &PathBuf, rows: &[MoonlinkRow]) -> Result<()> {
+        use arrow::record_batch::RecordBatch;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| Error::io_with_path(e, parent))?;
+        }
+
+        let batch = RecordBatch::try_new(self.schema.clone(), self.build_columns(rows)?)?;
+
+        let file = std::fs::File::create(path).map_err(|e| Error::io_with_path(e, path))?;
+        let mut writer = ArrowWriter::try_new(file, self.schema.clone(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Builds one Arrow array per field of `self.schema`, pulling the
+    /// matching [`RowValue`] out of each row by column position. Driven off
+    /// the table's actual schema rather than any fixed column layout, so it
+    /// works for any schema built from Int32, Int64, Float64, Utf8 and
+    /// Boolean columns, not only the (id, name, age) shape used by tests.
+    /// Any other column type is an error.
+    fn build_columns(&self, rows: &[MoonlinkRow]) -> Result<Vec<arrow::array::ArrayRef>> {
+        use arrow::array::{
+            ArrayRef, BooleanArray, Float64Array, Int32Array, Int64Array, StringArray,
+        };
+        use arrow_schema::DataType;
+
+        self.schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(col_idx, field)| {
+                let values = || rows.iter().map(|row| row.values().get(col_idx));
+                let array: ArrayRef = match field.data_type() {
+                    DataType::Int32 => Arc::new(
+                        values()
+                            .map(|v| match v {
+                                Some(RowValue::Int32(v)) => Some(*v),
+                                _ => None,
+                            })
+                            .collect::<Int32Array>(),
+                    ),
+                    DataType::Int64 => Arc::new(
+                        values()
+                            .map(|v| match v {
+                                Some(RowValue::Int64(v)) => Some(*v),
+                                _ => None,
+                            })
+                            .collect::<Int64Array>(),
+                    ),
+                    DataType::Float64 => Arc::new(
+                        values()
+                            .map(|v| match v {
+                                Some(RowValue::Float64(bits)) => Some(f64::from_bits(*bits)),
+                                _ => None,
+                            })
+                            .collect::<Float64Array>(),
+                    ),
+                    DataType::Utf8 => Arc::new(
+                        values()
+                            .map(|v| match v {
+                                Some(RowValue::ByteArray(bytes)) => {
+                                    Some(String::from_utf8_lossy(bytes).into_owned())
+                                }
+                                _ => None,
+                            })
+                            .collect::<StringArray>(),
+                    ),
+                    DataType::Boolean => Arc::new(
+                        values()
+                            .map(|v| match v {
+                                Some(RowValue::Bool(v)) => Some(*v),
+                                _ => None,
+                            })
+                            .collect::<BooleanArray>(),
+                    ),
+                    other => {
+                        return Err(Error::Other(format!(
+                            "field {} has unsupported column type {other:?}",
+                            field.name()
+                        )))
+                    }
+                };
+                Ok(array)
+            })
+            .collect()
+    }
+
+    /// Creates a mooncake snapshot at `lsn`: records the table's current
+    /// file set in the [`SnapshotList`] so a time-travel read can later be
+    /// opened against exactly this version via [`Self::open_snapshot_at_lsn`].
+    pub async fn create_mooncake_snapshot(&mut self, lsn: u64) -> Result<()> {
+        let files = self.compaction_manager.all_files();
+        let mut data_files = Vec::with_capacity(files.len());
+        let mut puffin_file_paths = Vec::new();
+        let mut deletion_vectors = Vec::new();
+        for (file_number, file) in files.iter().enumerate() {
+            data_files.push(DataFileForRead::new(
+                file.data_file_path.clone(),
+                file_number as u32,
+            ));
+            if let Some(puffin_path) = &file.deletion_vector_puffin_path {
+                let puffin_file_number = puffin_file_paths.len() as u32;
+                puffin_file_paths.push(puffin_path.clone());
+                deletion_vectors.push(moonlink_table_metadata::DeletionVector {
+                    data_file_number: file_number as u32,
+                    puffin_file_number,
+                });
+            }
+        }
+        self.snapshot_list.record_snapshot(
+            lsn,
+            data_files,
+            puffin_file_paths,
+            deletion_vectors,
+            Vec::new(),
+        );
+
+        if let Some(tx) = &self.completion_tx {
+            let _ = tx.send(TableEvent::SnapshotComplete { lsn }).await;
+        }
+        Ok(())
+    }
+
+    /// Opens a time-travel read as of `lsn`: the data files and deletion
+    /// vectors visible in the most recent mooncake snapshot at or before
+    /// `lsn`, pinned against compaction/eviction for as long as the
+    /// returned handle is alive.
+    pub fn open_snapshot_at_lsn(&self, lsn: u64) -> Option<SnapshotHandle> {
+        self.snapshot_list.open_at_lsn(lsn)
+    }
+
+    /// Expires recorded snapshots older than `cutoff_lsn`, mirroring an
+    /// iceberg snapshot-expiry sweep run at the same cutoff; see
+    /// [`snapshot_list`] for how the two interact.
+    pub fn expire_snapshots_older_than(&self, cutoff_lsn: u64) {
+        self.snapshot_list.expire_older_than(cutoff_lsn);
+    }
+
+    /// Opens a non-evictable cache handle for every deletion-vector puffin
+    /// file in the table's current file set, pinning each one against
+    /// eviction for as long as the returned handles are held. Honors the
+    /// table's configured [`crate::CacheReadMode`] (memory-mapped when mmap
+    /// mode is selected and storage is local, buffered otherwise) via
+    /// [`ObjectStorageCache::get_non_evictable_handle`].
+    pub fn open_deletion_vector_handles(&self) -> Result<Vec<NonEvictableHandle>> {
+        self.compaction_manager
+            .all_files()
+            .iter()
+            .filter_map(|file| file.deletion_vector_puffin_path.clone())
+            .map(|puffin_path| {
+                self.object_storage_cache
+                    .get_non_evictable_handle(puffin_path, self.filesystem_accessor.config())
+            })
+            .collect()
+    }
+}
+
+/// Scans `table_dir` for existing `{table_name}-{id}.parquet` data files and
+/// returns one past the highest `id` found, so a table reopened against a
+/// `table_dir` that already has flushed files doesn't reassign an in-use
+/// name and overwrite it on the next flush.
+fn next_file_id_after_existing(table_dir: &Path, table_name: &str) -> Result<u64> {
+    let prefix = format!("{table_name}-");
+    let entries = match std::fs::read_dir(table_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(Error::io_with_path(e, table_dir)),
+    };
+
+    let mut max_id = None;
+    for entry in entries {
+        let entry = entry.map_err(|e| Error::io_with_path(e, table_dir))?;
+        let Some(file_name) = entry.file_name().to_str().map(str::to_owned) else {
+            continue;
+        };
+        let Some(rest) = file_name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(".parquet"))
+        else {
+            continue;
+        };
+        if let Ok(id) = rest.parse::<u64>() {
+            max_id = Some(max_id.map_or(id, |current: u64| current.max(id)));
+        }
+    }
+    Ok(max_id.map_or(0, |id| id + 1))
+}
+
+/// Replays `wal_manager`'s log to rebuild the committed-but-possibly-unflushed
+/// batches a prior process had accepted before it stopped, so a reopened
+/// table doesn't silently lose writes that never made it into a parquet
+/// data file. Each WAL record holds the rows committed together under one
+/// LSN, encoded the same way [`MooncakeTable::commit`] wrote them.
+fn recover_committed_batches(wal_manager: &WalManager) -> Result<Vec<CommittedBatch>> {
+    let recovery = wal_manager.recover()?;
+    let mut batches = Vec::with_capacity(recovery.records.len());
+    for record in recovery.records {
+        let mut rows = Vec::new();
+        let mut offset = 0;
+        while offset < record.payload.len() {
+            let (row, consumed) = MoonlinkRow::decode_from(&record.payload[offset..]);
+            rows.push(row);
+            offset += consumed;
+        }
+        batches.push(CommittedBatch::InMemory {
+            lsn: record.lsn,
+            rows,
+        });
+    }
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::mooncake_table::table_creation_test_utils::*;
+    use crate::storage::mooncake_table::test_utils::{
+        append_commit_flush_create_mooncake_snapshot_for_test, test_iceberg_table_config, test_row,
+        TestContext,
+    };
+    use crate::storage::wal::test_utils::WAL_TEST_TABLE_ID;
+    use crate::WalConfig;
+
+    async fn new_test_table(context: &TestContext, table_name: &str) -> MooncakeTable {
+        let iceberg_table_config = test_iceberg_table_config(context, table_name);
+        let table_config =
+            MooncakeTableConfig::new(context.temp_dir.path().to_str().unwrap().to_string());
+        let wal_config = WalConfig::default_wal_config_local(WAL_TEST_TABLE_ID, &context.path());
+        let wal_manager = WalManager::new(&wal_config);
+        MooncakeTable::new(
+            (*create_test_arrow_schema()).clone(),
+            table_name.to_string(),
+            1,
+            context.path(),
+            iceberg_table_config.clone(),
+            table_config,
+            wal_manager,
+            create_test_object_storage_cache(&context.temp_dir),
+            create_test_filesystem_accessor(&iceberg_table_config),
+        )
+        .await
+        .unwrap()
+    }
+
+    /// Exercises open_snapshot_at_lsn/expire_snapshots_older_than end to
+    /// end: two mooncake snapshots recorded under distinct LSNs must coexist
+    /// in the retained snapshot list, a time-travel read between them must
+    /// resolve to the older one, and expiry must respect a live pin.
+    #[tokio::test]
+    async fn time_travels_across_multiple_retained_snapshots() {
+        let context = TestContext::new("snapshot_list_lifecycle");
+        let mut table = new_test_table(&context, "snap_test").await;
+        let mut completion_rx = table.subscribe_completion_events();
+
+        append_commit_flush_create_mooncake_snapshot_for_test(
+            &mut table,
+            &mut completion_rx,
+            vec![test_row(1, "a", 10)],
+            10,
+        )
+        .await
+        .unwrap();
+        append_commit_flush_create_mooncake_snapshot_for_test(
+            &mut table,
+            &mut completion_rx,
+            vec![test_row(2, "b", 20)],
+            20,
+        )
+        .await
+        .unwrap();
+
+        // lsn=15 falls strictly between the two recorded snapshots, so it
+        // must resolve to the most recent one at or before it: lsn=10.
+        let handle_at_15 = table
+            .open_snapshot_at_lsn(15)
+            .expect("a snapshot was recorded at or before lsn 15");
+        assert_eq!(handle_at_15.snapshot().lsn(), 10);
+        assert_eq!(handle_at_15.snapshot().data_files().len(), 1);
+
+        let handle_at_20 = table
+            .open_snapshot_at_lsn(20)
+            .expect("a snapshot was recorded at lsn 20");
+        assert_eq!(handle_at_20.snapshot().lsn(), 20);
+        assert_eq!(handle_at_20.snapshot().data_files().len(), 2);
+
+        // Expiring everything older than 20 must not drop the lsn=10
+        // snapshot while handle_at_15 still pins it.
+        table.expire_snapshots_older_than(20);
+        assert!(
+            table.open_snapshot_at_lsn(15).is_some(),
+            "a pinned snapshot must survive expiry"
+        );
+
+        drop(handle_at_15);
+        table.expire_snapshots_older_than(20);
+        assert!(
+            table.open_snapshot_at_lsn(15).is_none(),
+            "an unpinned, expired snapshot must be dropped"
+        );
+        assert!(table.open_snapshot_at_lsn(20).is_some());
+
+        drop(handle_at_20);
+    }
+
+    /// Reopening a table against a `table_dir` that already has a flushed
+    /// data file must not reassign that file's name: the new instance's
+    /// first flush has to pick up numbering after the existing file rather
+    /// than restarting from 0 and silently overwriting it.
+    #[tokio::test]
+    async fn reopened_table_does_not_reuse_an_existing_file_name() {
+        let context = TestContext::new("next_file_id_reopen");
+
+        let mut first_table = new_test_table(&context, "reopen_test").await;
+        let mut completion_rx = first_table.subscribe_completion_events();
+        append_commit_flush_create_mooncake_snapshot_for_test(
+            &mut first_table,
+            &mut completion_rx,
+            vec![test_row(1, "a", 10)],
+            10,
+        )
+        .await
+        .unwrap();
+        let first_file = first_table
+            .compaction_manager
+            .all_files()
+            .into_iter()
+            .next()
+            .unwrap()
+            .data_file_path;
+        let first_file_contents = std::fs::read(&first_file).unwrap();
+        drop(first_table);
+
+        let mut second_table = new_test_table(&context, "reopen_test").await;
+        let mut completion_rx = second_table.subscribe_completion_events();
+        append_commit_flush_create_mooncake_snapshot_for_test(
+            &mut second_table,
+            &mut completion_rx,
+            vec![test_row(2, "b", 20)],
+            20,
+        )
+        .await
+        .unwrap();
+
+        assert!(
+            std::path::Path::new(&first_file).exists(),
+            "the first table's flushed file must survive the second table's flush"
+        );
+        assert_eq!(
+            std::fs::read(&first_file).unwrap(),
+            first_file_contents,
+            "the first table's flushed file must not be overwritten"
+        );
+    }
+
+    /// A row that was committed but never flushed before the table was
+    /// dropped must still be visible after reopening: `MooncakeTable::new`
+    /// has to replay the WAL back into `committed_batches` rather than
+    /// starting from an empty set every time.
+    #[tokio::test]
+    async fn reopened_table_recovers_committed_but_unflushed_rows_from_the_wal() {
+        let context = TestContext::new("wal_replay_reopen");
+
+        let mut first_table = new_test_table(&context, "wal_replay_test").await;
+        first_table.append(test_row(1, "a", 10)).unwrap();
+        first_table.commit(10).unwrap();
+        drop(first_table);
+
+        let second_table = new_test_table(&context, "wal_replay_test").await;
+        assert_eq!(
+            second_table.committed_batches.len(),
+            1,
+            "the committed-but-unflushed batch must be recovered from the WAL"
+        );
+        assert_eq!(second_table.committed_batches[0].lsn(), 10);
+        match &second_table.committed_batches[0] {
+            CommittedBatch::InMemory { rows, .. } => {
+                assert_eq!(rows, &[test_row(1, "a", 10)]);
+            }
+            CommittedBatch::Spilled { .. } => panic!("expected an in-memory recovered batch"),
+        }
+    }
+}
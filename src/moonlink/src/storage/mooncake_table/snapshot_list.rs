@@ -0,0 +1,150 @@
+//! A retained-snapshot list enabling time-travel reads at an arbitrary
+//! committed LSN, modeled on LevelDB's `SnapshotList`.
+//!
+//! Every call to [`super::MooncakeTable::create_mooncake_snapshot`] records
+//! the table's current visible file set — its data files, their deletion
+//! vectors, and any raw position deletes — under that snapshot's LSN.
+//! [`SnapshotList::open_at_lsn`] hands back the most recent recorded file
+//! set at or before a requested LSN as a [`SnapshotHandle`]: as long as that
+//! handle is alive, compaction and cache eviction must not remove any file
+//! it references, even if a newer compaction pass has already rewritten
+//! them away from the *current* snapshot.
+//!
+//! ### Interaction with iceberg snapshot expiry
+//!
+//! Iceberg's own snapshot expiry independently decides how far back a table
+//! can time-travel at the catalog level. [`SnapshotList::expire_older_than`]
+//! should be called with the same cutoff LSN used for an iceberg expiry
+//! sweep, so the two stay in sync: a recorded entry is only ever dropped
+//! once it is both older than that cutoff *and* unpinned by any live
+//! [`SnapshotHandle`]. A pinned entry older than the cutoff is kept around
+//! (its files are not garbage collected) until the last handle referencing
+//! it is dropped, even though iceberg itself may have already expired the
+//! corresponding catalog snapshot — callers that need the two to be
+//! strictly consistent should hold a `SnapshotHandle` for the duration of
+//! any read that depends on the iceberg snapshot still existing.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use moonlink_table_metadata::{DeletionVector, PositionDelete};
+
+use crate::storage::mooncake_table::snapshot_read_output::DataFileForRead;
+
+/// The file set visible as of a single committed LSN.
+#[derive(Debug)]
+pub struct FileSetSnapshot {
+    lsn: u64,
+    data_files: Vec<DataFileForRead>,
+    puffin_file_paths: Vec<String>,
+    deletion_vectors: Vec<DeletionVector>,
+    position_deletes: Vec<PositionDelete>,
+}
+
+impl FileSetSnapshot {
+    pub fn lsn(&self) -> u64 {
+        self.lsn
+    }
+
+    pub fn data_files(&self) -> &[DataFileForRead] {
+        &self.data_files
+    }
+
+    pub fn puffin_file_paths(&self) -> &[String] {
+        &self.puffin_file_paths
+    }
+
+    pub fn deletion_vectors(&self) -> &[DeletionVector] {
+        &self.deletion_vectors
+    }
+
+    pub fn position_deletes(&self) -> &[PositionDelete] {
+        &self.position_deletes
+    }
+}
+
+/// A pin on a [`FileSetSnapshot`]: while this handle (or a clone of it) is
+/// alive, [`SnapshotList::expire_older_than`] will not drop the snapshot it
+/// points to, regardless of its LSN relative to the expiry cutoff.
+#[derive(Debug, Clone)]
+pub struct SnapshotHandle {
+    snapshot: Arc<FileSetSnapshot>,
+}
+
+impl SnapshotHandle {
+    pub fn snapshot(&self) -> &FileSetSnapshot {
+        &self.snapshot
+    }
+}
+
+/// Records every file set a table has committed and serves time-travel
+/// reads against them, reference-counting snapshots (via `Arc`) so that a
+/// live reader can't have its files garbage collected out from under it.
+#[derive(Debug, Default)]
+pub struct SnapshotList {
+    by_lsn: Mutex<BTreeMap<u64, Arc<FileSetSnapshot>>>,
+}
+
+impl SnapshotList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the file set visible as of `lsn`. Should be called once per
+    /// mooncake snapshot creation, after any compaction for that snapshot
+    /// has landed.
+    pub fn record_snapshot(
+        &self,
+        lsn: u64,
+        data_files: Vec<DataFileForRead>,
+        puffin_file_paths: Vec<String>,
+        deletion_vectors: Vec<DeletionVector>,
+        position_deletes: Vec<PositionDelete>,
+    ) {
+        let snapshot = Arc::new(FileSetSnapshot {
+            lsn,
+            data_files,
+            puffin_file_paths,
+            deletion_vectors,
+            position_deletes,
+        });
+        self.by_lsn.lock().unwrap().insert(lsn, snapshot);
+    }
+
+    /// Opens a time-travel read as of `lsn`: the most recently recorded
+    /// snapshot at or before `lsn`, pinned against removal for as long as
+    /// the returned handle is alive. Returns `None` if no snapshot has been
+    /// recorded at or before `lsn` (e.g. it predates the table, or has
+    /// already been expired and is unpinned).
+    pub fn open_at_lsn(&self, lsn: u64) -> Option<SnapshotHandle> {
+        let by_lsn = self.by_lsn.lock().unwrap();
+        let (_, snapshot) = by_lsn.range(..=lsn).next_back()?;
+        Some(SnapshotHandle {
+            snapshot: snapshot.clone(),
+        })
+    }
+
+    /// Drops every recorded snapshot older than `cutoff_lsn` that isn't
+    /// currently pinned by a live [`SnapshotHandle`]. Intended to be called
+    /// with the same cutoff used for an iceberg snapshot-expiry sweep; see
+    /// the module docs for how the two interact.
+    pub fn expire_older_than(&self, cutoff_lsn: u64) {
+        let mut by_lsn = self.by_lsn.lock().unwrap();
+        by_lsn.retain(|&lsn, snapshot| lsn >= cutoff_lsn || Arc::strong_count(snapshot) > 1);
+    }
+
+    /// Whether `file_path` is part of any currently recorded snapshot's file
+    /// set, live-pinned or not. Compaction and cache eviction should treat
+    /// this as the final word on whether a file is still needed before
+    /// deleting it from disk.
+    pub fn is_file_referenced(&self, file_path: &str) -> bool {
+        self.by_lsn
+            .lock()
+            .unwrap()
+            .values()
+            .any(|snapshot| {
+                snapshot.data_files.iter().any(|f| f.get_file_path() == file_path)
+                    || snapshot.puffin_file_paths.iter().any(|p| p == file_path)
+            })
+    }
+}
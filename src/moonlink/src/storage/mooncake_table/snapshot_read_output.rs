@@ -0,0 +1,25 @@
+/// A data file as surfaced to a read path: its on-disk location plus the
+/// bookkeeping needed to line it up with any deletion vectors that apply
+/// to it within a snapshot.
+#[derive(Debug, Clone)]
+pub struct DataFileForRead {
+    file_path: String,
+    file_number: u32,
+}
+
+impl DataFileForRead {
+    pub fn new(file_path: String, file_number: u32) -> Self {
+        Self {
+            file_path,
+            file_number,
+        }
+    }
+
+    pub fn get_file_path(&self) -> String {
+        self.file_path.clone()
+    }
+
+    pub fn file_number(&self) -> u32 {
+        self.file_number
+    }
+}
@@ -0,0 +1,374 @@
+//! Spill-to-disk support for oversized in-memory flush buffers.
+//!
+//! When the rows accumulated for a not-yet-flushed transaction exceed
+//! [`crate::storage::mooncake_table::MooncakeTableConfig::spill_threshold_bytes`],
+//! [`SpillManager`] writes the overflow batches out as temporary segment
+//! files so they don't have to be held in memory until flush. Segments are
+//! written with aligned, O_DIRECT-style I/O on Linux to avoid thrashing the
+//! page cache with data that is about to be rewritten into a parquet file
+//! anyway; other platforms fall back to plain buffered I/O.
+
+use std::alloc::{self, Layout};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::ptr::NonNull;
+
+use crate::row::MoonlinkRow;
+use crate::{Error, Result};
+
+/// Block size used to align spill I/O, matching the common Linux direct I/O
+/// device block size.
+const ALIGNMENT: usize = 4096;
+
+fn align_up(n: usize) -> usize {
+    n.div_ceil(ALIGNMENT) * ALIGNMENT
+}
+
+/// A heap buffer whose address (not just its length) is aligned to
+/// [`ALIGNMENT`]. O_DIRECT validates the user buffer's address in addition
+/// to the file offset/length; a plain `Vec<u8>` only guarantees the
+/// platform's default malloc alignment, which is not sufficient and makes
+/// O_DIRECT reads/writes fail with `EINVAL` on a real block device.
+struct AlignedBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+    layout: Layout,
+}
+
+impl AlignedBuffer {
+    /// Allocates a zeroed buffer of `len` bytes. `len` must already be a
+    /// multiple of [`ALIGNMENT`].
+    fn zeroed(len: usize) -> Self {
+        debug_assert_eq!(len % ALIGNMENT, 0, "aligned buffer length must be block-sized");
+        let layout = Layout::from_size_align(len, ALIGNMENT).expect("valid aligned layout");
+        // SAFETY: `layout` has non-zero size and `ALIGNMENT` is a valid
+        // power-of-two alignment.
+        let raw = unsafe { alloc::alloc_zeroed(layout) };
+        let ptr = NonNull::new(raw).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        Self { ptr, len, layout }
+    }
+}
+
+impl std::ops::Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: `ptr` points to `len` bytes allocated and zero-initialized
+        // by `zeroed`, owned exclusively by this buffer for its lifetime.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl std::ops::DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: see `Deref`; `&mut self` guarantees exclusive access.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` and `layout` are exactly as returned by the matching
+        // `alloc_zeroed` call in `zeroed`.
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
+
+/// Manages the spill directory for a single table: writing overflow row
+/// batches out as segment files and streaming them back in at flush time.
+#[derive(Debug)]
+pub struct SpillManager {
+    spill_dir: PathBuf,
+    reserved_disk_ratio: f64,
+    next_segment_id: u64,
+    use_direct_io: bool,
+}
+
+impl SpillManager {
+    /// Creates the spill directory for `table_dir` if needed and deletes any
+    /// residual segment files left behind by a crashed process.
+    pub fn new(table_dir: &Path, reserved_disk_ratio: f64) -> Result<Self> {
+        let spill_dir = table_dir.join("spill");
+        fs::create_dir_all(&spill_dir).map_err(|e| Error::io_with_path(e, &spill_dir))?;
+
+        let manager = Self {
+            spill_dir,
+            reserved_disk_ratio,
+            next_segment_id: 0,
+            use_direct_io: cfg!(target_os = "linux"),
+        };
+        manager.cleanup_stale_segments()?;
+        Ok(manager)
+    }
+
+    /// Deletes every segment file currently in the spill directory. Called on
+    /// startup (to clear files left over from a crash) and whenever a table
+    /// is dropped, since spilled rows are never meaningful across restarts.
+    pub fn cleanup_stale_segments(&self) -> Result<()> {
+        for entry in fs::read_dir(&self.spill_dir).map_err(|e| Error::io_with_path(e, &self.spill_dir))? {
+            let entry = entry.map_err(|e| Error::io_with_path(e, &self.spill_dir))?;
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("spill") {
+                fs::remove_file(&path).map_err(|e| Error::io_with_path(e, &path))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether spilling `additional_bytes` more would be safe given
+    /// `reserved_disk_ratio`: spilling is refused once the target volume's
+    /// free space would drop below that fraction of total capacity.
+    pub fn has_room_to_spill(&self, additional_bytes: u64) -> Result<bool> {
+        let (total, available) = disk_space(&self.spill_dir)?;
+        if total == 0 {
+            // Can't determine capacity (e.g. in a sandboxed test environment);
+            // don't block spilling on an unknown quantity.
+            return Ok(true);
+        }
+        let available_after = available.saturating_sub(additional_bytes);
+        let reserved = (total as f64 * self.reserved_disk_ratio) as u64;
+        Ok(available_after >= reserved)
+    }
+
+    fn next_segment_path(&mut self) -> PathBuf {
+        let id = self.next_segment_id;
+        self.next_segment_id += 1;
+        self.spill_dir.join(format!("segment-{id:020}.spill"))
+    }
+
+    /// Spills `rows` to a new segment file and returns its path. Rows are
+    /// encoded with [`MoonlinkRow::encode_into`], written in aligned blocks
+    /// sized to [`ALIGNMENT`], and followed by a trailing footer block
+    /// recording the true (unpadded) payload length so a short final block
+    /// can be truncated back out on read.
+    pub fn spill_rows(&mut self, rows: &[MoonlinkRow]) -> Result<PathBuf> {
+        let mut encoded = Vec::new();
+        for row in rows {
+            row.encode_into(&mut encoded);
+        }
+
+        let path = self.next_segment_path();
+        let logical_len = encoded.len();
+        let padded_len = align_up(logical_len.max(1));
+
+        // The payload is `padded_len` bytes of (possibly padded) row data
+        // followed by one footer block whose first 8 bytes record the true
+        // (unpadded) length, allocated as a single sector-aligned buffer so
+        // the whole thing can be handed straight to an O_DIRECT write.
+        let mut payload = AlignedBuffer::zeroed(padded_len + ALIGNMENT);
+        payload[..logical_len].copy_from_slice(&encoded);
+        payload[padded_len..padded_len + 8].copy_from_slice(&(logical_len as u64).to_le_bytes());
+
+        let mut file = open_aligned(&path, self.use_direct_io, true)?;
+        file.write_all(&payload)
+            .map_err(|e| Error::io_with_path(e, &path))?;
+        file.sync_all().map_err(|e| Error::io_with_path(e, &path))?;
+
+        Ok(path)
+    }
+
+    /// Reads back every row previously written to `path` by [`Self::spill_rows`]
+    /// and deletes the segment file.
+    pub fn read_and_remove_segment(&self, path: &Path) -> Result<Vec<MoonlinkRow>> {
+        let rows = read_segment(path, self.use_direct_io)?;
+        fs::remove_file(path).map_err(|e| Error::io_with_path(e, path))?;
+        Ok(rows)
+    }
+}
+
+impl Drop for SpillManager {
+    fn drop(&mut self) {
+        let _ = self.cleanup_stale_segments();
+    }
+}
+
+fn read_segment(path: &Path, use_direct_io: bool) -> Result<Vec<MoonlinkRow>> {
+    let mut file = open_aligned(path, use_direct_io, false)?;
+    let file_len = file
+        .metadata()
+        .map_err(|e| Error::io_with_path(e, path))?
+        .len() as usize;
+    if file_len < ALIGNMENT || !file_len.is_multiple_of(ALIGNMENT) {
+        return Err(Error::Other(format!(
+            "spill segment {} is not a whole number of {ALIGNMENT}-byte blocks",
+            path.display()
+        )));
+    }
+
+    let mut buf = AlignedBuffer::zeroed(file_len);
+    file.read_exact(&mut buf)
+        .map_err(|e| Error::io_with_path(e, path))?;
+
+    let footer = &buf[file_len - ALIGNMENT..];
+    let logical_len = u64::from_le_bytes(footer[0..8].try_into().unwrap()) as usize;
+    let data = &buf[..logical_len];
+
+    let mut rows = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let (row, consumed) = MoonlinkRow::decode_from(&data[offset..]);
+        rows.push(row);
+        offset += consumed;
+    }
+    Ok(rows)
+}
+
+/// Opens `path` for aligned (ideally O_DIRECT) I/O when `use_direct_io` is
+/// set and the platform supports it, falling back to ordinary buffered I/O
+/// otherwise (e.g. non-Linux, or filesystems that reject O_DIRECT such as
+/// tmpfs on some kernels).
+fn open_aligned(path: &Path, use_direct_io: bool, create: bool) -> Result<File> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true);
+    if create {
+        options.create(true).truncate(true);
+    }
+
+    #[cfg(target_os = "linux")]
+    if use_direct_io {
+        use std::os::unix::fs::OpenOptionsExt;
+        let direct_options = {
+            let mut o = options.clone();
+            o.custom_flags(libc::O_DIRECT);
+            o
+        };
+        if let Ok(file) = direct_options.open(path) {
+            return Ok(file);
+        }
+        // Fall through to buffered open below if O_DIRECT was rejected
+        // (e.g. unsupported filesystem).
+    }
+    let _ = use_direct_io;
+
+    options
+        .open(path)
+        .map_err(|e| Error::io_with_path(e, path))
+}
+
+#[cfg(target_os = "linux")]
+fn disk_space(path: &Path) -> Result<(u64, u64)> {
+    use std::ffi::CString;
+    use std::mem::MaybeUninit;
+
+    let c_path = CString::new(path.as_os_str().to_str().unwrap_or("."))
+        .map_err(|e| Error::Other(e.to_string()))?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `c_path` is a valid NUL-terminated string and `stat` is sized
+    // for `libc::statvfs`; `statvfs` only writes into `stat` on success.
+    let rc = unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) };
+    if rc != 0 {
+        return Err(Error::PlainIo(std::io::Error::last_os_error()));
+    }
+    // SAFETY: `statvfs` returned success, so `stat` is fully initialized.
+    let stat = unsafe { stat.assume_init() };
+    let block_size = stat.f_frsize;
+    Ok((block_size * stat.f_blocks, block_size * stat.f_bavail))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn disk_space(_path: &Path) -> Result<(u64, u64)> {
+    Ok((0, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::row::RowValue;
+    use tempfile::tempdir;
+
+    fn test_rows(n: usize) -> Vec<MoonlinkRow> {
+        (0..n as i32)
+            .map(|id| {
+                MoonlinkRow::new(vec![
+                    RowValue::Int32(id),
+                    RowValue::ByteArray(format!("row-{id}").into_bytes()),
+                ])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spill_rows_roundtrips_through_read_and_remove_segment() {
+        let dir = tempdir().unwrap();
+        let mut manager = SpillManager::new(dir.path(), 0.0).unwrap();
+        let rows = test_rows(50);
+
+        let path = manager.spill_rows(&rows).unwrap();
+        assert!(path.exists());
+
+        let read_back = manager.read_and_remove_segment(&path).unwrap();
+        assert_eq!(read_back, rows);
+        assert!(!path.exists(), "segment file should be deleted after read");
+    }
+
+    #[test]
+    fn successive_spills_get_distinct_segment_paths() {
+        let dir = tempdir().unwrap();
+        let mut manager = SpillManager::new(dir.path(), 0.0).unwrap();
+
+        let first_path = manager.spill_rows(&test_rows(1)).unwrap();
+        let second_path = manager.spill_rows(&test_rows(1)).unwrap();
+
+        assert_ne!(first_path, second_path);
+    }
+
+    #[test]
+    fn cleanup_stale_segments_removes_leftover_spill_files() {
+        let dir = tempdir().unwrap();
+        let mut manager = SpillManager::new(dir.path(), 0.0).unwrap();
+        let path = manager.spill_rows(&test_rows(1)).unwrap();
+        assert!(path.exists());
+
+        manager.cleanup_stale_segments().unwrap();
+        assert!(
+            !path.exists(),
+            "a segment left over from a crashed process must be cleaned up"
+        );
+    }
+
+    #[test]
+    fn new_manager_cleans_up_segments_left_by_a_prior_process() {
+        let dir = tempdir().unwrap();
+        {
+            let mut manager = SpillManager::new(dir.path(), 0.0).unwrap();
+            manager.spill_rows(&test_rows(1)).unwrap();
+            // A crashed process never runs `Drop`, so skip the drop-time
+            // cleanup and leave the segment on disk.
+            std::mem::forget(manager);
+        }
+
+        // A fresh manager opened against the same spill dir (simulating a
+        // restart after a crash) must not see the previous process's segment.
+        let manager = SpillManager::new(dir.path(), 0.0).unwrap();
+        let leftover = fs::read_dir(dir.path().join("spill"))
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().and_then(|e| e.to_str()) == Some("spill"))
+            .count();
+        assert_eq!(leftover, 0);
+        drop(manager);
+    }
+
+    #[test]
+    fn align_up_rounds_up_to_the_next_block_boundary() {
+        assert_eq!(align_up(0), 0);
+        assert_eq!(align_up(1), ALIGNMENT);
+        assert_eq!(align_up(ALIGNMENT), ALIGNMENT);
+        assert_eq!(align_up(ALIGNMENT + 1), 2 * ALIGNMENT);
+    }
+
+    #[test]
+    fn has_room_to_spill_refuses_when_reserve_would_be_violated() {
+        let dir = tempdir().unwrap();
+        // A reserved ratio of 1.0 means no space may ever be spent, so even a
+        // tiny additional write must be refused (when disk space can be
+        // determined at all; sandboxed environments may report total == 0,
+        // in which case the check is skipped and spilling is always allowed).
+        let manager = SpillManager::new(dir.path(), 1.0).unwrap();
+        let (total, _) = disk_space(&manager.spill_dir).unwrap();
+        if total > 0 {
+            assert!(!manager.has_room_to_spill(1).unwrap());
+        }
+    }
+}
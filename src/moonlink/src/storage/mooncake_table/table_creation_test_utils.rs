@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use arrow_schema::{DataType, Field, Schema};
+use tempfile::TempDir;
+
+use crate::storage::filesystem::{AccessorConfig, FileSystemAccessor};
+use crate::storage::iceberg::iceberg_table_config::IcebergTableConfig;
+use crate::storage::object_storage_cache::ObjectStorageCache;
+
+/// Test util function to build the `(id, name, age)` schema used throughout
+/// mooncake table tests.
+pub fn create_test_arrow_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, true),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("age", DataType::Int32, true),
+    ]))
+}
+
+/// Test util function to get an object storage cache rooted in a fresh temp
+/// directory under `parent_dir`.
+pub fn create_test_object_storage_cache(parent_dir: &TempDir) -> ObjectStorageCache {
+    let cache_dir = tempfile::tempdir_in(parent_dir.path()).unwrap();
+    ObjectStorageCache::new(cache_dir)
+}
+
+/// Test util function to get a filesystem accessor for the given iceberg
+/// table's data accessor config.
+pub fn create_test_filesystem_accessor(
+    iceberg_table_config: &IcebergTableConfig,
+) -> FileSystemAccessor {
+    let config: AccessorConfig = iceberg_table_config.data_accessor_config.clone();
+    FileSystemAccessor::new(config)
+}
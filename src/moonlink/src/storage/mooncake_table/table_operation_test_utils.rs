@@ -0,0 +1,37 @@
+use tokio::sync::mpsc::Receiver;
+
+use crate::storage::mooncake_table::{MooncakeTable, TableEvent};
+use crate::Result;
+
+/// Test util function to flush `table` at `lsn` and block until the
+/// corresponding [`TableEvent::FlushComplete`] is observed.
+pub async fn flush_table_and_sync(
+    table: &mut MooncakeTable,
+    completion_rx: &mut Receiver<TableEvent>,
+    lsn: u64,
+) -> Result<()> {
+    table.flush(lsn).await?;
+    while let Some(event) = completion_rx.recv().await {
+        if let TableEvent::FlushComplete { lsn: flushed_lsn } = event {
+            assert_eq!(flushed_lsn, lsn);
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Test util function to create a mooncake snapshot at `lsn` and block until
+/// the corresponding [`TableEvent::SnapshotComplete`] is observed.
+pub async fn create_mooncake_snapshot_for_test(
+    table: &mut MooncakeTable,
+    completion_rx: &mut Receiver<TableEvent>,
+    lsn: u64,
+) {
+    table.create_mooncake_snapshot(lsn).await.unwrap();
+    while let Some(event) = completion_rx.recv().await {
+        if let TableEvent::SnapshotComplete { lsn: snapshot_lsn } = event {
+            assert_eq!(snapshot_lsn, lsn);
+            break;
+        }
+    }
+}
@@ -188,9 +188,9 @@ pub async fn append_commit_flush_create_mooncake_snapshot_for_test(
     lsn: u64,
 ) -> Result<()> {
     append_rows(table, rows)?;
-    table.commit(lsn);
+    table.commit(lsn)?;
     flush_table_and_sync(table, completion_rx, lsn).await?;
-    create_mooncake_snapshot_for_test(table, completion_rx).await;
+    create_mooncake_snapshot_for_test(table, completion_rx, lsn).await;
     Ok(())
 }
 
@@ -0,0 +1,186 @@
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use memmap2::Mmap;
+use tempfile::TempDir;
+
+use crate::storage::filesystem::accessor_config::{AccessorConfig, CacheReadMode};
+use crate::{Error, Result};
+
+/// A cache of data files and puffin deletion-vector blobs pulled from remote
+/// (or local) object storage and kept on local disk for fast repeated reads.
+///
+/// The real cache implements LRU eviction keyed by file id; for now this is
+/// the minimal surface moonlink's read path and tests depend on.
+#[derive(Debug, Clone)]
+pub struct ObjectStorageCache {
+    cache_dir: Arc<TempDir>,
+}
+
+impl ObjectStorageCache {
+    pub fn new(cache_dir: TempDir) -> Self {
+        Self {
+            cache_dir: Arc::new(cache_dir),
+        }
+    }
+
+    pub fn cache_directory(&self) -> PathBuf {
+        self.cache_dir.path().to_path_buf()
+    }
+
+    /// Pins `cache_filepath` against eviction and opens it for reading per
+    /// `accessor_config`'s [`CacheReadMode`]: memory-mapped when the backend
+    /// is local and mmap mode is requested, buffered otherwise.
+    pub fn get_non_evictable_handle(
+        &self,
+        cache_filepath: impl Into<String>,
+        accessor_config: &AccessorConfig,
+    ) -> Result<NonEvictableHandle> {
+        NonEvictableHandle::open(cache_filepath.into(), accessor_config.effective_cache_read_mode())
+    }
+}
+
+/// Either an owned buffer or a borrowed slice into a memory-mapped file;
+/// either way, derefs to the file's bytes.
+enum FileBacking {
+    Buffered(Vec<u8>),
+    Mapped(Arc<Mmap>),
+}
+
+impl FileBacking {
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            FileBacking::Buffered(bytes) => bytes,
+            FileBacking::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+impl std::fmt::Debug for FileBacking {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileBacking::Buffered(bytes) => {
+                f.debug_tuple("Buffered").field(&bytes.len()).finish()
+            }
+            FileBacking::Mapped(mmap) => f.debug_tuple("Mapped").field(&mmap.len()).finish(),
+        }
+    }
+}
+
+/// A handle to a cache entry that is guaranteed not to be evicted while the
+/// handle is alive. Clones share the same backing; dropping the last clone
+/// releases the pin, and if the entry was memory-mapped, the mapping is
+/// unmapped at the same time — never before, since the handle and its
+/// clones are the only owners of the `Mmap` they wrap.
+#[derive(Debug, Clone)]
+pub struct NonEvictableHandle {
+    cache_filepath: String,
+    backing: Arc<FileBacking>,
+}
+
+impl NonEvictableHandle {
+    fn open(cache_filepath: String, read_mode: CacheReadMode) -> Result<Self> {
+        let backing = match read_mode {
+            CacheReadMode::Mmap => {
+                let file = std::fs::File::open(&cache_filepath)
+                    .map_err(|e| Error::io_with_path(e, &cache_filepath))?;
+                // SAFETY: moonlink writes data and puffin files once and
+                // never truncates or rewrites them in place while they are
+                // mapped; external modification of a
+                // mapped file is the one precondition `Mmap::map` can't
+                // enforce itself.
+                let mmap = unsafe { Mmap::map(&file) }
+                    .map_err(|e| Error::io_with_path(e, &cache_filepath))?;
+                FileBacking::Mapped(Arc::new(mmap))
+            }
+            CacheReadMode::Buffered => {
+                let bytes = std::fs::read(&cache_filepath)
+                    .map_err(|e| Error::io_with_path(e, &cache_filepath))?;
+                FileBacking::Buffered(bytes)
+            }
+        };
+        Ok(Self {
+            cache_filepath,
+            backing: Arc::new(backing),
+        })
+    }
+
+    /// Test/in-memory construction with no backing file; only the path is
+    /// meaningful. Real handles are created via
+    /// [`ObjectStorageCache::get_non_evictable_handle`].
+    pub fn new(cache_filepath: impl Into<String>) -> Self {
+        Self {
+            cache_filepath: cache_filepath.into(),
+            backing: Arc::new(FileBacking::Buffered(Vec::new())),
+        }
+    }
+
+    pub fn get_cache_filepath(&self) -> &str {
+        &self.cache_filepath
+    }
+
+    /// Returns the cached file's bytes, borrowed for as long as this handle
+    /// (or a clone of it) is alive.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.backing.as_bytes()
+    }
+}
+
+impl Deref for NonEvictableHandle {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::filesystem::storage_config::StorageConfig;
+
+    fn local_accessor_config(root_directory: &std::path::Path) -> AccessorConfig {
+        AccessorConfig::new_with_storage_config(StorageConfig::FileSystem {
+            root_directory: root_directory.to_str().unwrap().to_string(),
+            atomic_write_dir: None,
+        })
+    }
+
+    #[test]
+    fn buffered_handle_reads_whole_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("blob.bin");
+        std::fs::write(&file_path, b"buffered contents").unwrap();
+
+        let cache = ObjectStorageCache::new(tempfile::tempdir().unwrap());
+        let accessor_config = local_accessor_config(dir.path());
+
+        let handle = cache
+            .get_non_evictable_handle(file_path.to_str().unwrap(), &accessor_config)
+            .unwrap();
+        assert_eq!(handle.as_bytes(), b"buffered contents");
+    }
+
+    #[test]
+    fn mmap_handle_stays_valid_after_backing_file_is_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("blob.bin");
+        std::fs::write(&file_path, b"mapped contents").unwrap();
+
+        let cache = ObjectStorageCache::new(tempfile::tempdir().unwrap());
+        let accessor_config =
+            local_accessor_config(dir.path()).with_cache_read_mode(CacheReadMode::Mmap);
+
+        let handle = cache
+            .get_non_evictable_handle(file_path.to_str().unwrap(), &accessor_config)
+            .unwrap();
+
+        // Simulate the cache evicting the on-disk entry from under the
+        // handle: the mapping keeps the underlying inode's pages alive via
+        // the still-open mapping until the handle itself is dropped.
+        std::fs::remove_file(&file_path).unwrap();
+
+        assert_eq!(handle.as_bytes(), b"mapped contents");
+    }
+}
@@ -0,0 +1,59 @@
+//! A small standalone CRC32C (Castagnoli) implementation.
+//!
+//! WAL records are checksummed with CRC32C rather than the IEEE CRC32
+//! variant because it's what most modern storage engines (and our own
+//! spill segment footer, eventually) use — it has dedicated hardware
+//! instructions on current CPUs, though this implementation is the portable
+//! table-driven form.
+
+const POLY: u32 = 0x82f6_3b78; // Bit-reversed form of the Castagnoli polynomial 0x1EDC6F41.
+
+fn build_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ POLY
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Computes the CRC32C checksum of `bytes`.
+pub fn crc32c(bytes: &[u8]) -> u32 {
+    // Rebuilt per call rather than cached in a `static`/`OnceLock` to keep
+    // this module free of shared mutable state; the table is tiny (1 KiB)
+    // and this is not a hot loop relative to the disk I/O around it.
+    let table = build_table();
+    let mut crc = 0xffff_ffffu32;
+    for &byte in bytes {
+        let idx = ((crc ^ byte as u32) & 0xff) as usize;
+        crc = table[idx] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_known_vector() {
+        // "123456789" is the standard CRC32C test vector.
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(crc32c(b""), 0);
+    }
+}
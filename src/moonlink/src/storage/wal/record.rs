@@ -0,0 +1,93 @@
+//! On-disk framing for a single WAL record, with a CRC32C checksum over the
+//! payload so torn or corrupted tail writes can be detected during replay.
+//!
+//! Layout: `[u32 payload_len][u32 crc32c][u64 lsn][payload_len bytes]`.
+
+use super::crc32c::crc32c;
+
+const HEADER_LEN: usize = 4 + 4 + 8;
+
+/// A single logical entry in the WAL: the LSN it's associated with and an
+/// opaque, caller-defined payload (e.g. a serialized batch of appended rows).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalRecord {
+    pub lsn: u64,
+    pub payload: Vec<u8>,
+}
+
+impl WalRecord {
+    pub fn new(lsn: u64, payload: Vec<u8>) -> Self {
+        Self { lsn, payload }
+    }
+
+    /// Encodes this record's on-disk framing, including its header checksum.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(HEADER_LEN + self.payload.len());
+        buf.extend_from_slice(&(self.payload.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&crc32c(&self.payload).to_le_bytes());
+        buf.extend_from_slice(&self.lsn.to_le_bytes());
+        buf.extend_from_slice(&self.payload);
+        buf
+    }
+}
+
+/// The outcome of scanning `buf` for a prefix of valid, checksummed records.
+#[derive(Debug, Default)]
+pub struct ScanResult {
+    pub records: Vec<WalRecord>,
+    /// The byte offset at which scanning stopped: either end-of-file (every
+    /// record was valid) or the start of the first record whose declared
+    /// length ran past end-of-file or whose checksum didn't match.
+    pub valid_prefix_len: usize,
+    /// Set if scanning stopped before reaching end-of-file because a record
+    /// was torn or corrupted, i.e. the WAL file has an unrecoverable tail
+    /// past `valid_prefix_len`.
+    pub has_truncated_tail: bool,
+    /// The LSN of the last record that validated, if any. The table should
+    /// resume replay from just after this LSN.
+    pub last_valid_lsn: Option<u64>,
+}
+
+/// Scans `buf` for a maximal prefix of valid records, stopping at the first
+/// record whose checksum fails or whose declared length runs past
+/// end-of-file — mirroring how a log replayer handles a partial final block
+/// left behind by a torn write.
+pub fn scan_records(buf: &[u8]) -> ScanResult {
+    let mut result = ScanResult::default();
+    let mut offset = 0;
+
+    loop {
+        if offset == buf.len() {
+            break;
+        }
+        if buf.len() - offset < HEADER_LEN {
+            result.has_truncated_tail = true;
+            break; // Torn header: not even enough bytes for the length/crc/lsn fields.
+        }
+
+        let payload_len =
+            u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap()) as usize;
+        let expected_crc = u32::from_le_bytes(buf[offset + 4..offset + 8].try_into().unwrap());
+        let lsn = u64::from_le_bytes(buf[offset + 8..offset + 16].try_into().unwrap());
+
+        let payload_start = offset + HEADER_LEN;
+        let payload_end = payload_start + payload_len;
+        if payload_end > buf.len() {
+            result.has_truncated_tail = true;
+            break; // Declared length runs past end-of-file.
+        }
+
+        let payload = &buf[payload_start..payload_end];
+        if crc32c(payload) != expected_crc {
+            result.has_truncated_tail = true;
+            break; // Corrupted record: checksum mismatch.
+        }
+
+        result.records.push(WalRecord::new(lsn, payload.to_vec()));
+        result.last_valid_lsn = Some(lsn);
+        offset = payload_end;
+    }
+
+    result.valid_prefix_len = offset;
+    result
+}
@@ -0,0 +1,2 @@
+/// Fixed table id used by WAL tests so generated file names are predictable.
+pub const WAL_TEST_TABLE_ID: u32 = 0;
@@ -0,0 +1,22 @@
+use std::path::{Path, PathBuf};
+
+/// Configuration for a single table's [`super::WalManager`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalConfig {
+    pub table_id: u32,
+    pub wal_directory: PathBuf,
+}
+
+impl WalConfig {
+    pub fn new(table_id: u32, wal_directory: PathBuf) -> Self {
+        Self {
+            table_id,
+            wal_directory,
+        }
+    }
+
+    /// Test util function to get a WAL config rooted under `test_dir`.
+    pub fn default_wal_config_local(table_id: u32, test_dir: &Path) -> Self {
+        Self::new(table_id, test_dir.join("wal"))
+    }
+}